@@ -1,5 +1,5 @@
 use anyhow::Result;
-use cnxt::{ColoredString, Colorize as _};
+use cnxt::{Colorize as _, StyledText};
 use crossterm::terminal::size;
 use image::{
     GenericImageView as _, Rgba, imageops::FilterType, load_from_memory,
@@ -16,7 +16,9 @@ fn main() {
     }
 }
 
-fn img2lines(buffer: &[u8]) -> Result<Vec<String>> {
+type RowPair = (Vec<Rgba<u8>>, Option<Vec<Rgba<u8>>>);
+
+fn img2lines(buffer: &[u8]) -> Result<Vec<StyledText<'static>>> {
     let mut image = load_from_memory(buffer)?;
     let (width, height) = image.dimensions();
     let termsize = size()?;
@@ -34,7 +36,7 @@ fn img2lines(buffer: &[u8]) -> Result<Vec<String>> {
         (height as f64 * zoom) as u32,
         FilterType::CatmullRom,
     );
-    let pixels = image.pixels().map(|p| p).collect::<Vec<_>>();
+    let pixels = image.pixels().collect::<Vec<_>>();
     let mut pixels_2d: Vec<Vec<Rgba<u8>>> = Vec::new();
     for pixel in pixels {
         let (x, y) = (pixel.0, pixel.1);
@@ -43,7 +45,7 @@ fn img2lines(buffer: &[u8]) -> Result<Vec<String>> {
         };
         pixels_2d.last_mut().unwrap().push(image.get_pixel(x, y));
     }
-    let pixel_2d_pairs: Vec<(Vec<Rgba<u8>>, Option<Vec<Rgba<u8>>>)> = pixels_2d
+    let pixel_2d_pairs: Vec<RowPair> = pixels_2d
         .chunks(2)
         .map(|chunk| {
             let row1 = chunk[0].clone();
@@ -56,15 +58,16 @@ fn img2lines(buffer: &[u8]) -> Result<Vec<String>> {
         })
         .collect();
 
-    let mut lines: Vec<String> = Vec::new();
+    let mut lines: Vec<StyledText> = Vec::new();
     for (row1, row2) in pixel_2d_pairs {
-        let mut line = String::new();
+        let mut line = StyledText::with_capacity(row1.len());
         if let Some(row2) = row2 {
             for i in 0..row1.len() - 1 {
-                let block: ColoredString = "▀"
-                    .truecolor(row1[i][0], row1[i][1], row1[i][2])
-                    .on_truecolor(row2[i][0], row2[i][1], row2[i][2]);
-                line = format!("{}{}", line, block);
+                line.push(
+                    "▀"
+                        .truecolor(row1[i][0], row1[i][1], row1[i][2])
+                        .on_truecolor(row2[i][0], row2[i][1], row2[i][2]),
+                );
             }
         }
         lines.push(line);
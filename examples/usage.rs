@@ -6,10 +6,9 @@ fn main() {
 
     let print_src = |s: &str| {
         println!(
-            "\n{}{}{}",
-            r#"> println!("{}", "#,
-            s.bright_yellow(),
-            r#");"#
+            r#"
+> println!("{{}}", {});"#,
+            s.bright_yellow()
         );
     };
     println!(
@@ -65,13 +64,10 @@ fn main() {
                  )"#,
     );
     println!(
-        "  {}",
-        format!(
-            "{} {} {}",
-            "Multiple".cyan(),
-            "formats".italic().yellow(),
-            "combined".bright_magenta().bold()
-        )
+        "  {} {} {}",
+        "Multiple".cyan(),
+        "formats".italic().yellow(),
+        "combined".bright_magenta().bold()
     );
 
     print_src(r#""Reset style to default".red().bold().clear()"#);
@@ -0,0 +1,389 @@
+//! Procedural macros of [cnxt](https://docs.rs/cnxt).
+//!
+//! Don't depend on this crate directly, enable the `macros` feature of
+//! cnxt and use `cnxt::cformat!` and friends, which are documented there.
+
+use std::collections::BTreeSet;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    Ident, LitStr, Token,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+};
+
+/// Expands markup in a format string to the escape codes of its tags.
+///
+/// Called by the `macro_rules!` macros of cnxt as
+/// `format_markup!($crate, kind, "format string", args...)`, where `kind`
+/// is the `std` macro to expand to: `format`, `print`, `println`, `eprint`
+/// or `eprintln`.
+#[proc_macro]
+pub fn format_markup(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as Input);
+    match input.expand() {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Input {
+    krate: TokenTree,
+    kind: Ident,
+    template: LitStr,
+    args: TokenStream2,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let kind = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let template = input.parse()?;
+
+        let mut args = TokenStream2::new();
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            args = input.parse()?;
+        }
+
+        Ok(Self {
+            krate,
+            kind,
+            template,
+            args,
+        })
+    }
+}
+
+impl Input {
+    fn expand(&self) -> syn::Result<TokenStream2> {
+        let Self {
+            krate,
+            kind,
+            template,
+            args,
+        } = self;
+
+        let stream = match kind.to_string().as_str() {
+            "format" | "print" | "println" => quote!(Stdout),
+            "eprint" | "eprintln" => quote!(Stderr),
+            _ => return Err(syn::Error::new(kind.span(), "unknown macro")),
+        };
+
+        let markup = parse_markup(&template.value())
+            .map_err(|message| syn::Error::new(template.span(), message))?;
+
+        // Keep the span of the literal, so arguments captured by name still
+        // resolve at the call site
+        let format = LitStr::new(&markup.format, template.span());
+
+        let level = Ident::new("level", Span::mixed_site());
+        let transitions = markup.transitions.iter().enumerate().map(
+            |(idx, (from, to))| {
+                let name = format_ident!("__cnxt_{idx}");
+                let from = from.to_tokens(krate);
+                let to = to.to_tokens(krate);
+                quote! {
+                    #name = #krate::__private::Markup::new(#level, #from, #to),
+                }
+            },
+        );
+
+        let mut args = args.clone();
+        let ends_with_comma = matches!(
+            args.clone().into_iter().last(),
+            Some(TokenTree::Punct(p)) if p.as_char() == ','
+        );
+        if !args.is_empty() && !ends_with_comma {
+            args.extend(quote!(,));
+        }
+
+        Ok(quote! {{
+            let #level = #krate::control::get_color_level_for(
+                #krate::control::Stream::#stream,
+            );
+            #krate::__private::#kind!(#format, #args #(#transitions)*)
+        }})
+    }
+}
+
+/// A format string with the tags replaced by named arguments.
+struct Markup {
+    format: String,
+    /// The states before and after each named argument `__cnxt_{idx}`.
+    transitions: Vec<(State, State)>,
+}
+
+/// Parses the tags of `template`, leaving the format placeholders alone.
+fn parse_markup(template: &str) -> Result<Markup, String> {
+    let mut markup = Markup {
+        format: String::with_capacity(template.len()),
+        transitions: Vec::new(),
+    };
+    let mut open: Vec<(&str, Tag)> = Vec::new();
+    let mut state = State::default();
+
+    let mut rest = template;
+    while let Some(idx) = rest.find(['<', '{']) {
+        markup.format.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        if let Some(after) = rest.strip_prefix("<<") {
+            markup.format.push('<');
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("{{") {
+            markup.format.push_str("{{");
+            rest = after;
+            continue;
+        }
+        if rest.starts_with('{') {
+            // A placeholder may contain `<` as its alignment, e.g. `{:<8}`
+            let end = rest.find('}').ok_or("unclosed `{` in format string")?;
+            markup.format.push_str(&rest[..=end]);
+            rest = &rest[end + 1..];
+            continue;
+        }
+
+        let end = rest
+            .find('>')
+            .ok_or("unclosed tag, write `<<` for a literal `<`")?;
+        let content = rest[1..end].trim();
+        rest = &rest[end + 1..];
+
+        if let Some(name) = content.strip_prefix('/') {
+            let name = name.trim();
+            let (opening, _) = open
+                .pop()
+                .ok_or_else(|| format!("unexpected `</{name}>`"))?;
+            if !name.is_empty() && name != opening {
+                return Err(format!(
+                    "`</{name}>` does not match the open tag `<{opening}>`"
+                ));
+            }
+        } else {
+            open.push((content, Tag::parse(content)?));
+        }
+
+        let next = State::of(open.iter().map(|(_, tag)| tag));
+        if next != state {
+            let idx = markup.transitions.len();
+            markup.format.push_str(&format!("{{__cnxt_{idx}}}"));
+            markup.transitions.push((state, next.clone()));
+            state = next;
+        }
+    }
+    markup.format.push_str(rest);
+
+    // Tags that are still open end with the text
+    if state != State::default() {
+        let idx = markup.transitions.len();
+        markup.format.push_str(&format!("{{__cnxt_{idx}}}"));
+        markup.transitions.push((state, State::default()));
+    }
+
+    Ok(markup)
+}
+
+/// The colors and styles of a tag, e.g. `<bold red on black>`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Tag {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    styles: BTreeSet<&'static str>,
+}
+
+impl Tag {
+    /// Parses the words of a tag, like `StyleSpec::from_str` but without
+    /// color functions.
+    fn parse(content: &str) -> Result<Self, String> {
+        if content.is_empty() {
+            return Err("empty tag".to_owned());
+        }
+
+        let mut tag = Self::default();
+        let mut words = content.split_whitespace();
+        while let Some(word) = words.next() {
+            let word = word.to_ascii_lowercase();
+            if word == "on" {
+                let color =
+                    next_color(&mut words).ok_or("missing color after `on`")?;
+                tag.bg = Some(Color::parse(&color)?);
+            } else if let Some(color) = word.strip_prefix("on_") {
+                tag.bg = Some(Color::parse(color)?);
+            } else if let Some(style) =
+                style_variant(&without_separators(&word))
+            {
+                add_style(&mut tag.styles, style);
+            } else {
+                let color = with_bright_prefix(word, &mut words);
+                tag.fg = Some(Color::parse(&color)?);
+            }
+        }
+
+        Ok(tag)
+    }
+}
+
+/// Adds the `Styles` variant `style`. An underline style replaces the other
+/// ones, like the underline builders of `ColoredString`, since a terminal
+/// shows only one of them.
+fn add_style(styles: &mut BTreeSet<&'static str>, style: &'static str) {
+    if style.ends_with("Underline") {
+        styles.retain(|other| !other.ends_with("Underline"));
+    }
+    styles.insert(style);
+}
+
+fn next_color<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<String> {
+    let word = words.next()?.to_ascii_lowercase();
+    Some(with_bright_prefix(word, words))
+}
+
+/// Joins `bright` with the following word, so `bright red` is one color.
+fn with_bright_prefix<'a>(
+    word: String,
+    words: &mut impl Iterator<Item = &'a str>,
+) -> String {
+    if word == "bright"
+        && let Some(next) = words.next()
+    {
+        format!("bright_{}", next.to_ascii_lowercase())
+    } else {
+        word
+    }
+}
+
+/// Removes the separators of a lowercase style or color name, as
+/// `Styles::from_str` and `Color::from_str` do.
+fn without_separators(word: &str) -> String {
+    word.chars().filter(|&c| !matches!(c, '_' | '-')).collect()
+}
+
+macro_rules! style_names {
+    ($($($name:literal)|+ => $variant:ident,)*) => {
+        /// Returns the `Styles` variant called `name`.
+        fn style_variant(name: &str) -> Option<&'static str> {
+            match name {
+                $($($name)|+ => Some(stringify!($variant)),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+cnxt_shared::style_names!(style_names);
+
+macro_rules! color_names {
+    ($($($name:literal)|+ => $variant:ident,)*) => {
+        /// Returns the `Color` variant called `name`.
+        fn color_variant(name: &str) -> Option<&'static str> {
+            match name {
+                $($($name)|+ => Some(stringify!($variant)),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+cnxt_shared::color_names!(color_names);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    /// One of the 16 standard colors, by its `Color` variant.
+    Named(&'static str),
+    Ansi256(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Parses a color name, `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa` or
+    /// `ansi256(n)`.
+    fn parse(word: &str) -> Result<Self, String> {
+        if word.starts_with('#') {
+            return cnxt_shared::parse_hex(word)
+                .map(|[r, g, b]| Self::Rgb(r, g, b))
+                .ok_or_else(|| format!("invalid hex color `{word}`"));
+        }
+        if let Some(idx) = word
+            .strip_prefix("ansi256(")
+            .and_then(|s| s.strip_suffix(')'))
+        {
+            return idx
+                .trim()
+                .parse()
+                .map(Self::Ansi256)
+                .map_err(|_| format!("invalid palette index `{idx}`"));
+        }
+
+        let name = without_separators(word);
+        if let Some(variant) = color_variant(&name) {
+            return Ok(Self::Named(variant));
+        }
+        cnxt_shared::css_color(&name)
+            .map(|(r, g, b)| Self::Rgb(r, g, b))
+            .ok_or_else(|| format!("unknown style or color `{word}`"))
+    }
+
+    fn to_tokens(self, krate: &TokenTree) -> TokenStream2 {
+        match self {
+            Self::Named(variant) => {
+                let variant = Ident::new(variant, Span::call_site());
+                quote!(#krate::Color::#variant)
+            }
+            Self::Ansi256(idx) => quote!(#krate::Color::Ansi256 { idx: #idx }),
+            Self::Rgb(r, g, b) => {
+                quote!(#krate::Color::TrueColor { r: #r, g: #g, b: #b })
+            }
+        }
+    }
+}
+
+/// The combined colors and styles of the open tags.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct State(Tag);
+
+impl State {
+    /// Combines `tags`, the colors of later ones taking precedence.
+    fn of<'a>(tags: impl Iterator<Item = &'a Tag>) -> Self {
+        let mut state = Tag::default();
+        for tag in tags {
+            state.fg = tag.fg.or(state.fg);
+            state.bg = tag.bg.or(state.bg);
+            for style in &tag.styles {
+                add_style(&mut state.styles, style);
+            }
+        }
+        Self(state)
+    }
+
+    fn to_tokens(&self, krate: &TokenTree) -> TokenStream2 {
+        let option = |color: Option<Color>| match color {
+            Some(color) => {
+                let color = color.to_tokens(krate);
+                quote!(::core::option::Option::Some(#color))
+            }
+            None => quote!(::core::option::Option::None),
+        };
+        let fg = option(self.0.fg);
+        let bg = option(self.0.bg);
+        let styles = self
+            .0
+            .styles
+            .iter()
+            .map(|style| Ident::new(style, Span::call_site()));
+
+        quote! {
+            #krate::__private::MarkupState {
+                styles: &[#(#krate::Styles::#styles),*],
+                fg: #fg,
+                bg: #bg,
+            }
+        }
+    }
+}
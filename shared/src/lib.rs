@@ -0,0 +1,111 @@
+//! Name tables and parsers shared by [cnxt](https://docs.rs/cnxt) and
+//! cnxt-macros, so the tags of `cformat!` are checked at compile time with
+//! the same names cnxt parses at runtime.
+//!
+//! Don't depend on this crate directly, it is an implementation detail of
+//! cnxt.
+
+#![no_std]
+
+mod css_colors;
+
+pub use css_colors::css_color;
+
+/// Calls `$callback!` with the names of the styles and the `Styles` variant
+/// each names, as `"name" | "alias" => Variant,` entries. Names are
+/// lowercase, without spaces, `_` or `-`.
+#[macro_export]
+macro_rules! style_names {
+    ($callback:ident) => {
+        $callback! {
+            "clear" | "normal" => Clear,
+            "bold" => Bold,
+            "dimmed" | "dim" => Dimmed,
+            "italic" => Italic,
+            "underline" => Underline,
+            "blink" => Blink,
+            "reversed" | "reverse" => Reversed,
+            "hidden" => Hidden,
+            "strikethrough" | "strike" => Strikethrough,
+            "curlyunderline" | "curly" => CurlyUnderline,
+            "doubleunderline" | "double" => DoubleUnderline,
+            "dottedunderline" | "dotted" => DottedUnderline,
+            "dashedunderline" | "dashed" => DashedUnderline,
+            "overline" => Overline,
+            "rapidblink" => RapidBlink,
+            "framed" => Framed,
+            "encircled" => Encircled,
+            "superscript" => Superscript,
+            "subscript" => Subscript,
+        }
+    };
+}
+
+/// Calls `$callback!` with the names of the 16 standard colors and the
+/// `Color` variant each names, in the format of [`style_names!`].
+#[macro_export]
+macro_rules! color_names {
+    ($callback:ident) => {
+        $callback! {
+            "black" => Black,
+            "red" => Red,
+            "green" => Green,
+            "yellow" => Yellow,
+            "blue" => Blue,
+            "magenta" | "purple" => Magenta,
+            "cyan" => Cyan,
+            "white" => White,
+            "brightblack" => BrightBlack,
+            "brightred" => BrightRed,
+            "brightgreen" => BrightGreen,
+            "brightyellow" => BrightYellow,
+            "brightblue" => BrightBlue,
+            "brightmagenta" | "brightpurple" => BrightMagenta,
+            "brightcyan" => BrightCyan,
+            "brightwhite" => BrightWhite,
+        }
+    };
+}
+
+/// Parses a `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa` code, with or without
+/// the `#`, into its red, green and blue channels. The alpha digits are
+/// checked but ignored.
+#[must_use]
+pub const fn parse_hex(hex: &str) -> Option<[u8; 3]> {
+    let digits = match hex.as_bytes() {
+        [b'#', digits @ ..] => digits,
+        digits => digits,
+    };
+    if !matches!(digits.len(), 3 | 4 | 6 | 8) {
+        return None;
+    }
+
+    let mut values = [0; 8];
+    let mut idx = 0;
+    while idx < digits.len() {
+        values[idx] = match hex_digit(digits[idx]) {
+            Some(value) => value,
+            None => return None,
+        };
+        idx += 1;
+    }
+
+    if digits.len() >= 6 {
+        Some([
+            values[0] * 16 + values[1],
+            values[2] * 16 + values[3],
+            values[4] * 16 + values[5],
+        ])
+    } else {
+        Some([values[0] * 17, values[1] * 17, values[2] * 17])
+    }
+}
+
+const fn hex_digit(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
@@ -1,6 +1,16 @@
-use std::borrow::Cow;
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+use core::{error::Error, fmt, str::FromStr};
+#[cfg(feature = "std")]
+use std::sync::LazyLock;
 
-use crate::control::{ColorLevel, get_current_color_level};
+use crate::{
+    colorspace::{
+        Lab, OkLab, ciede2000, hsl_to_rgb, oklab_distance_sq, rgb_to_lab,
+        rgb_to_oklab,
+    },
+    control::{ColorLevel, get_color_distance, get_current_color_level},
+    math,
+};
 
 const ANSI_16_COLORS: [(u8, u8, u8, Color); 16] = [
     (0, 0, 0, Color::Black),
@@ -51,7 +61,32 @@ pub enum Color {
 impl<'a> Color {
     #[must_use]
     pub fn to_fg_str(&self) -> Cow<'a, str> {
-        match *self {
+        self.to_fg_str_at(get_current_color_level())
+    }
+
+    #[must_use]
+    pub fn to_bg_str(&self) -> Cow<'a, str> {
+        self.to_bg_str_at(get_current_color_level())
+    }
+
+    #[must_use]
+    pub fn to_underline_str(&self) -> Cow<'a, str> {
+        self.to_underline_str_at(get_current_color_level())
+    }
+
+    /// Returns the SGR parameters for this color as a foreground color,
+    /// downgraded to fit `level` instead of the current color level.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, control::ColorLevel};
+    ///
+    /// let orange = Color::TrueColor { r: 255, g: 136, b: 0 };
+    /// assert_eq!(orange.to_fg_str_at(ColorLevel::TrueColor), "38;2;255;136;0");
+    /// assert_eq!(orange.to_fg_str_at(ColorLevel::Ansi256), "38;5;208");
+    /// ```
+    #[must_use]
+    pub fn to_fg_str_at(self, level: ColorLevel) -> Cow<'a, str> {
+        match self {
             Self::Black => "30".into(),
             Self::Red => "31".into(),
             Self::Green => "32".into(),
@@ -69,17 +104,23 @@ impl<'a> Color {
             Self::BrightCyan => "96".into(),
             Self::BrightWhite => "97".into(),
             Self::Ansi256 { idx } => format!("38;5;{idx}").into(),
-            Self::TrueColor { r, g, b } => match get_current_color_level() {
-                ColorLevel::Ansi16 => self.fallback_to_ansi16().to_fg_str(),
-                ColorLevel::Ansi256 => self.fallback_to_ansi256().to_fg_str(),
+            Self::TrueColor { r, g, b } => match level {
+                ColorLevel::Ansi16 => {
+                    self.fallback_to_ansi16().to_fg_str_at(level)
+                }
+                ColorLevel::Ansi256 => {
+                    self.fallback_to_ansi256().to_fg_str_at(level)
+                }
                 _ => format!("38;2;{r};{g};{b}").into(),
             },
         }
     }
 
+    /// Returns the SGR parameters for this color as a background color,
+    /// downgraded to fit `level` instead of the current color level.
     #[must_use]
-    pub fn to_bg_str(&self) -> Cow<'a, str> {
-        match *self {
+    pub fn to_bg_str_at(self, level: ColorLevel) -> Cow<'a, str> {
+        match self {
             Self::Black => "40".into(),
             Self::Red => "41".into(),
             Self::Green => "42".into(),
@@ -96,75 +137,605 @@ impl<'a> Color {
             Self::BrightMagenta => "105".into(),
             Self::BrightCyan => "106".into(),
             Self::BrightWhite => "107".into(),
-            Self::Ansi256 { idx } => match get_current_color_level() {
-                ColorLevel::Ansi16 => self.fallback_to_ansi16().to_bg_str(),
+            Self::Ansi256 { idx } => match level {
+                ColorLevel::Ansi16 => {
+                    self.fallback_to_ansi16().to_bg_str_at(level)
+                }
                 _ => format!("48;5;{idx}").into(),
             },
-            Self::TrueColor { r, g, b } => match get_current_color_level() {
-                ColorLevel::Ansi16 => self.fallback_to_ansi16().to_bg_str(),
-                ColorLevel::Ansi256 => self.fallback_to_ansi256().to_bg_str(),
+            Self::TrueColor { r, g, b } => match level {
+                ColorLevel::Ansi16 => {
+                    self.fallback_to_ansi16().to_bg_str_at(level)
+                }
+                ColorLevel::Ansi256 => {
+                    self.fallback_to_ansi256().to_bg_str_at(level)
+                }
                 _ => format!("48;2;{r};{g};{b}").into(),
             },
         }
     }
 
+    /// Returns the SGR parameters for this color as an underline color,
+    /// downgraded to fit `level` the same way as [`Color::to_fg_str_at`].
+    ///
+    /// Underline colors have no 16-color form, so the basic colors are
+    /// written as their index in the 256-color palette.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, control::ColorLevel};
+    ///
+    /// assert_eq!(Color::BrightRed.to_underline_str_at(ColorLevel::Ansi16), "58;5;9");
+    ///
+    /// let orange = Color::TrueColor { r: 255, g: 136, b: 0 };
+    /// assert_eq!(orange.to_underline_str_at(ColorLevel::TrueColor), "58;2;255;136;0");
+    /// assert_eq!(orange.to_underline_str_at(ColorLevel::Ansi256), "58;5;208");
+    /// ```
+    #[must_use]
+    pub fn to_underline_str_at(self, level: ColorLevel) -> Cow<'a, str> {
+        match self {
+            Self::Ansi256 { idx } => format!("58;5;{idx}").into(),
+            Self::TrueColor { r, g, b } => match level {
+                ColorLevel::Ansi16 => {
+                    self.fallback_to_ansi16().to_underline_str_at(level)
+                }
+                ColorLevel::Ansi256 => {
+                    self.fallback_to_ansi256().to_underline_str_at(level)
+                }
+                _ => format!("58;2;{r};{g};{b}").into(),
+            },
+            basic => {
+                let idx = ANSI_16_COLORS
+                    .iter()
+                    .position(|&(.., color)| color == basic)
+                    .unwrap_or_default();
+                format!("58;5;{idx}").into()
+            }
+        }
+    }
+
     /// Converts a `TrueColor` or `Ansi256` Color to the closest ANSI 16-color palette color.
     ///
+    /// The distance is measured with the globally configured
+    /// [`ColorDistance`], see [`set_color_distance()`].
+    ///
     /// Returns self if not a `TrueColor` or `Ansi256` Color.
+    ///
+    /// [`set_color_distance()`]: crate::control::set_color_distance
     #[must_use]
     pub fn fallback_to_ansi16(self) -> Self {
-        let (r, g, b) = match self {
+        self.fallback_to_ansi16_with(get_color_distance())
+    }
+
+    /// Same as [`Color::fallback_to_ansi16`], but measures the distance with
+    /// `distance` instead of the global setting.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, ColorDistance};
+    ///
+    /// let violet = Color::TrueColor { r: 128, g: 0, b: 255 };
+    /// assert_eq!(violet.fallback_to_ansi16_with(ColorDistance::Rgb), Color::Magenta);
+    /// assert_eq!(
+    ///     violet.fallback_to_ansi16_with(ColorDistance::OkLab),
+    ///     Color::BrightBlue
+    /// );
+    /// ```
+    #[must_use]
+    pub fn fallback_to_ansi16_with(self, distance: ColorDistance) -> Self {
+        let rgb = match self {
             Self::Ansi256 { idx } => ansi256_to_rgb(idx),
             Self::TrueColor { r, g, b } => (r, g, b),
             _ => return self,
         };
-        let mut min_distance_sq = u32::MAX;
-        let mut closest_color = self;
-
-        for &(cr, cg, cb, color) in &ANSI_16_COLORS {
-            let dr = (i32::from(r) - i32::from(cr)).pow(2) as u32;
-            let dg = (i32::from(g) - i32::from(cg)).pow(2) as u32;
-            let db = (i32::from(b) - i32::from(cb)).pow(2) as u32;
-            let distance_sq = dr + dg + db;
-
-            if distance_sq < min_distance_sq {
-                min_distance_sq = distance_sq;
-                closest_color = color;
-            }
-        }
 
-        closest_color
+        ANSI_16_COLORS[distance.closest(rgb, 16)].3
     }
 
     /// Converts a `TrueColor` to the closest ANSI 256-color palette color.
     ///
+    /// The distance is measured with the globally configured
+    /// [`ColorDistance`], see [`set_color_distance()`].
+    ///
     /// Returns self if not a TrueColor.
+    ///
+    /// [`set_color_distance()`]: crate::control::set_color_distance
     #[must_use]
     pub fn fallback_to_ansi256(self) -> Self {
-        let (r, g, b) = match self {
+        self.fallback_to_ansi256_with(get_color_distance())
+    }
+
+    /// Same as [`Color::fallback_to_ansi256`], but measures the distance with
+    /// `distance` instead of the global setting.
+    #[must_use]
+    pub fn fallback_to_ansi256_with(self, distance: ColorDistance) -> Self {
+        let rgb = match self {
             Self::TrueColor { r, g, b } => (r, g, b),
             _ => return self,
         };
-        let mut min_distance_sq = u32::MAX;
-        let mut closest_idx = 0;
-
-        for idx in 0u8..=255 {
-            let (cr, cg, cb) = ansi256_to_rgb(idx);
-            let dr = (i32::from(r) - i32::from(cr)).pow(2) as u32;
-            let dg = (i32::from(g) - i32::from(cg)).pow(2) as u32;
-            let db = (i32::from(b) - i32::from(cb)).pow(2) as u32;
-            let distance_sq = dr + dg + db;
-
-            if distance_sq < min_distance_sq {
-                min_distance_sq = distance_sq;
-                closest_idx = idx;
+
+        Self::Ansi256 {
+            idx: distance.closest(rgb, 256) as u8,
+        }
+    }
+
+    /// Returns the RGB value of this color.
+    ///
+    /// The 16 standard colors and `Ansi256` colors are looked up in the
+    /// palette that is used for downgrading.
+    ///
+    /// ```rust
+    /// use cnxt::Color;
+    ///
+    /// assert_eq!(Color::BrightRed.to_rgb(), (255, 0, 0));
+    /// assert_eq!(Color::Ansi256 { idx: 208 }.to_rgb(), (255, 135, 0));
+    /// ```
+    #[must_use]
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Self::Ansi256 { idx } => ansi256_to_rgb(idx),
+            Self::TrueColor { r, g, b } => (r, g, b),
+            basic => ANSI_16_COLORS
+                .iter()
+                .find(|&&(.., color)| color == basic)
+                .map(|&(r, g, b, _)| (r, g, b))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Creates a color from a `#rgb` or `#rrggbb` hex code, in a `const`
+    /// context where [`Color::from_str`] can't be called.
+    ///
+    /// The `#` is optional, and the alpha digits of `#rgba` and `#rrggbbaa`
+    /// are ignored. Evaluating an invalid code in a `const` or `static` is a
+    /// compile error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hex` is not a valid hex code.
+    ///
+    /// ```rust
+    /// use cnxt::Color;
+    ///
+    /// const ACCENT: Color = Color::from_hex_const("#ff8800");
+    /// assert_eq!(ACCENT, Color::TrueColor { r: 255, g: 136, b: 0 });
+    /// ```
+    ///
+    /// ```compile_fail
+    /// const TYPO: cnxt::Color = cnxt::Color::from_hex_const("#ff880");
+    /// ```
+    ///
+    /// [`Color::from_str`]: #method.from_str
+    #[must_use]
+    pub const fn from_hex_const(hex: &str) -> Self {
+        match parse_hex(hex) {
+            Some(color) => color,
+            None => panic!("invalid hex color"),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    /// Formats the color in a form that [`Color::from_str`] parses back:
+    /// the name for the 16 standard colors, like `red` or `bright_red`,
+    /// `ansi256(n)` for palette colors and `#rrggbb` for true colors.
+    ///
+    /// ```rust
+    /// use cnxt::Color;
+    ///
+    /// assert_eq!(Color::BrightRed.to_string(), "bright_red");
+    /// assert_eq!(Color::Ansi256 { idx: 208 }.to_string(), "ansi256(208)");
+    /// assert_eq!(
+    ///     Color::TrueColor { r: 255, g: 136, b: 0 }.to_string(),
+    ///     "#ff8800"
+    /// );
+    /// ```
+    ///
+    /// [`Color::from_str`]: #method.from_str
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            Self::Black => "black",
+            Self::Red => "red",
+            Self::Green => "green",
+            Self::Yellow => "yellow",
+            Self::Blue => "blue",
+            Self::Magenta => "magenta",
+            Self::Cyan => "cyan",
+            Self::White => "white",
+            Self::BrightBlack => "bright_black",
+            Self::BrightRed => "bright_red",
+            Self::BrightGreen => "bright_green",
+            Self::BrightYellow => "bright_yellow",
+            Self::BrightBlue => "bright_blue",
+            Self::BrightMagenta => "bright_magenta",
+            Self::BrightCyan => "bright_cyan",
+            Self::BrightWhite => "bright_white",
+            Self::Ansi256 { idx } => return write!(f, "ansi256({idx})"),
+            Self::TrueColor { r, g, b } => {
+                return write!(f, "#{r:02x}{g:02x}{b:02x}");
+            }
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Serialized as a string, see the [`Display`](fmt::Display)
+/// implementation.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Color {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserialized from any string [`Color::from_str`] accepts.
+///
+/// [`Color::from_str`]: #method.from_str
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses a color from a name, hex code, CSS color function or palette
+    /// index.
+    ///
+    /// Accepted forms, case-insensitive:
+    /// - The 16 standard colors, with spaces, `_` or `-` as separators:
+    ///   `red`, `bright red`, `bright_red`. `purple` is an alias for magenta.
+    /// - The CSS/X11 named colors, such as `orange` or `rebeccapurple`. The
+    ///   standard colors take precedence, so `red` is [`Color::Red`].
+    /// - `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`. Alpha is ignored.
+    /// - `rgb(r, g, b)` and `rgba(r, g, b, a)` with channels as `0..=255` or
+    ///   percentages, and `hsl(h, s%, l%)` and `hsla(h, s%, l%, a)`. Commas,
+    ///   spaces and `/` are all accepted as separators. Alpha must be in
+    ///   `0..=1` or a percentage, and is ignored.
+    /// - `ansi256(n)` or a plain index `n`, for the 256-color palette.
+    ///
+    /// ```rust
+    /// use cnxt::Color;
+    ///
+    /// assert_eq!("bright_red".parse(), Ok(Color::BrightRed));
+    /// assert_eq!("Bright Red".parse(), Ok(Color::BrightRed));
+    /// assert_eq!("orange".parse(), Ok(Color::TrueColor { r: 255, g: 165, b: 0 }));
+    /// assert_eq!("#f80".parse(), Ok(Color::TrueColor { r: 255, g: 136, b: 0 }));
+    /// assert_eq!("rgb(255 136 0 / 50%)".parse(), Ok(Color::TrueColor { r: 255, g: 136, b: 0 }));
+    /// assert_eq!("hsl(120, 100%, 25%)".parse(), Ok(Color::TrueColor { r: 0, g: 128, b: 0 }));
+    /// assert_eq!("ansi256(208)".parse(), Ok(Color::Ansi256 { idx: 208 }));
+    /// assert_eq!("208".parse(), Ok(Color::Ansi256 { idx: 208 }));
+    /// assert!("#ff88".parse::<Color>().is_ok());
+    /// assert!("not a color".parse::<Color>().is_err());
+    /// assert_eq!(
+    ///     "rgba(1, 2, 3, foo)".parse::<Color>(),
+    ///     Err(cnxt::ParseColorError::InvalidArguments)
+    /// );
+    /// assert_eq!(
+    ///     "hsla(0, 0%, 0%, 2)".parse::<Color>(),
+    ///     Err(cnxt::ParseColorError::OutOfRange)
+    /// );
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        if s.is_empty() {
+            return Err(ParseColorError::Empty);
+        }
+
+        if s.starts_with('#') {
+            return parse_hex(&s).ok_or(ParseColorError::InvalidHex);
+        }
+
+        if let Some((function, args)) =
+            s.strip_suffix(')').and_then(|s| s.split_once('('))
+        {
+            return parse_function(function.trim(), args);
+        }
+
+        if s.bytes().all(|b| b.is_ascii_digit()) {
+            return parse_index(&s);
+        }
+
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .collect();
+        if let Some(color) = color_named(&name) {
+            return Ok(color);
+        }
+
+        let (r, g, b) = cnxt_shared::css_color(&name)
+            .ok_or(ParseColorError::UnknownName)?;
+        Ok(Self::TrueColor { r, g, b })
+    }
+}
+
+macro_rules! color_names {
+    ($($($name:literal)|+ => $variant:ident,)*) => {
+        /// Returns the standard color called `name`, which is lowercase and
+        /// without separators.
+        fn color_named(name: &str) -> Option<Color> {
+            match name {
+                $($($name)|+ => Some(Color::$variant),)*
+                _ => None,
             }
         }
+    };
+}
 
-        Self::Ansi256 { idx: closest_idx }
+cnxt_shared::color_names!(color_names);
+
+/// Parses a `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa` code, with or without
+/// the `#`. The alpha digits are checked but ignored.
+///
+/// This is a `const fn` so the `from_hex_const` constructors can share it
+/// with [`Color::from_str`] and the `hexcolor` methods.
+pub(crate) const fn parse_hex(hex: &str) -> Option<Color> {
+    match cnxt_shared::parse_hex(hex) {
+        Some([r, g, b]) => Some(Color::TrueColor { r, g, b }),
+        None => None,
     }
 }
 
+fn parse_index(s: &str) -> Result<Color, ParseColorError> {
+    let idx = s
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| ParseColorError::InvalidArguments)?;
+    let idx = u8::try_from(idx).map_err(|_| ParseColorError::OutOfRange)?;
+    Ok(Color::Ansi256 { idx })
+}
+
+fn parse_function(
+    function: &str,
+    args: &str,
+) -> Result<Color, ParseColorError> {
+    let args: Vec<&str> = args
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect();
+
+    match (function, args.as_slice()) {
+        ("ansi256", [idx]) => parse_index(idx),
+        ("rgb" | "rgba", [r, g, b, alpha @ ..]) if alpha.len() <= 1 => {
+            check_alpha(alpha.first().copied())?;
+            let channel = |arg: &str| {
+                let value = match arg.strip_suffix('%') {
+                    Some(percent) => parse_number(percent)? * 2.55,
+                    None => parse_number(arg)?,
+                };
+                if (0.0..=255.0).contains(&value) {
+                    Ok(math::round(value) as u8)
+                } else {
+                    Err(ParseColorError::OutOfRange)
+                }
+            };
+            Ok(Color::TrueColor {
+                r: channel(r)?,
+                g: channel(g)?,
+                b: channel(b)?,
+            })
+        }
+        ("hsl" | "hsla", [h, s, l, alpha @ ..]) if alpha.len() <= 1 => {
+            check_alpha(alpha.first().copied())?;
+            let hue = parse_number(h.strip_suffix("deg").unwrap_or(h))?;
+            let percent = |arg: &str| {
+                let value = parse_number(arg.strip_suffix('%').unwrap_or(arg))?;
+                if (0.0..=100.0).contains(&value) {
+                    Ok(value / 100.0)
+                } else {
+                    Err(ParseColorError::OutOfRange)
+                }
+            };
+            let (r, g, b) = hsl_to_rgb(hue, percent(s)?, percent(l)?);
+            Ok(Color::TrueColor { r, g, b })
+        }
+        ("ansi256" | "rgb" | "rgba" | "hsl" | "hsla", _) => {
+            Err(ParseColorError::InvalidArguments)
+        }
+        _ => Err(ParseColorError::UnknownName),
+    }
+}
+
+/// Checks the alpha argument of `rgba()` and `hsla()`, a number in `0..=1`
+/// or a percentage, even though it is ignored.
+fn check_alpha(alpha: Option<&str>) -> Result<(), ParseColorError> {
+    let Some(alpha) = alpha else {
+        return Ok(());
+    };
+    let (value, max) = match alpha.strip_suffix('%') {
+        Some(percent) => (parse_number(percent)?, 100.0),
+        None => (parse_number(alpha)?, 1.0),
+    };
+    if (0.0..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(ParseColorError::OutOfRange)
+    }
+}
+
+fn parse_number(s: &str) -> Result<f32, ParseColorError> {
+    s.parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or(ParseColorError::InvalidArguments)
+}
+
+/// An error which can be returned when parsing a [`Color`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseColorError {
+    /// The string was empty.
+    Empty,
+    /// The string is not a known color name or color function.
+    UnknownName,
+    /// A `#` code does not have 3, 4, 6 or 8 hex digits.
+    InvalidHex,
+    /// A color function has the wrong number of arguments, or an argument
+    /// is not a number.
+    InvalidArguments,
+    /// A channel, percentage or palette index is out of range.
+    OutOfRange,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Empty => "cannot parse color from empty string",
+            Self::UnknownName => "unknown color name",
+            Self::InvalidHex => "invalid hex color code",
+            Self::InvalidArguments => "invalid color function arguments",
+            Self::OutOfRange => "color value out of range",
+        })
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// How the difference between two colors is measured when a color is
+/// downgraded to a smaller palette.
+///
+/// Plain RGB distance is cheap but often picks visibly wrong colors, e.g.
+/// oranges end up red and dark grays end up blue. The perceptual metrics
+/// match what the eye sees much more closely.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorDistance {
+    /// Squared euclidean distance in sRGB.
+    #[default]
+    Rgb,
+    /// Euclidean distance in the OKLab color space.
+    OkLab,
+    /// The CIEDE2000 color difference in the CIELAB color space.
+    Ciede2000,
+}
+
+#[cfg(feature = "std")]
+static OKLAB_PALETTE: LazyLock<[OkLab; 256]> = LazyLock::new(oklab_palette);
+
+#[cfg(feature = "std")]
+static LAB_PALETTE: LazyLock<[Lab; 256]> = LazyLock::new(lab_palette);
+
+fn oklab_palette() -> [OkLab; 256] {
+    core::array::from_fn(|idx| rgb_to_oklab(ansi256_to_rgb(idx as u8)))
+}
+
+fn lab_palette() -> [Lab; 256] {
+    core::array::from_fn(|idx| rgb_to_lab(ansi256_to_rgb(idx as u8)))
+}
+
+impl ColorDistance {
+    /// Returns the index of the color closest to `target` among the first
+    /// `len` colors of the 256-color palette.
+    ///
+    /// On ties the lowest index wins.
+    fn closest(self, target: (u8, u8, u8), len: usize) -> usize {
+        match self {
+            Self::Rgb if len == 256 => closest_ansi256_rgb(target),
+            Self::Rgb => first_min((0..len).map(|idx| {
+                rgb_distance_sq(target, ansi256_to_rgb(idx as u8)) as f32
+            })),
+            Self::OkLab => {
+                // Without std there is nowhere to cache the palette
+                #[cfg(feature = "std")]
+                let palette = &*OKLAB_PALETTE;
+                #[cfg(not(feature = "std"))]
+                let palette = &oklab_palette();
+
+                let target = rgb_to_oklab(target);
+                first_min(
+                    palette[..len]
+                        .iter()
+                        .map(|&color| oklab_distance_sq(target, color)),
+                )
+            }
+            Self::Ciede2000 => {
+                #[cfg(feature = "std")]
+                let palette = &*LAB_PALETTE;
+                #[cfg(not(feature = "std"))]
+                let palette = &lab_palette();
+
+                let target = rgb_to_lab(target);
+                first_min(
+                    palette[..len]
+                        .iter()
+                        .map(|&color| ciede2000(target, color)),
+                )
+            }
+        }
+    }
+}
+
+fn first_min(distances: impl Iterator<Item = f32>) -> usize {
+    let mut min_distance = f32::MAX;
+    let mut closest = 0;
+    for (idx, distance) in distances.enumerate() {
+        if distance < min_distance {
+            min_distance = distance;
+            closest = idx;
+        }
+    }
+
+    closest
+}
+
+/// Finds the closest 256-color palette index by RGB distance without
+/// scanning the whole palette.
+///
+/// Only three candidates can win: one of the 16 standard colors, the
+/// nearest point of the 6x6x6 cube and the nearest step of the gray ramp.
+/// They are compared in index order so ties resolve the same way as an
+/// exhaustive search would.
+fn closest_ansi256_rgb(target: (u8, u8, u8)) -> usize {
+    let mut closest = 0;
+    let mut min_distance = u32::MAX;
+    let mut consider = |idx: usize| {
+        let distance = rgb_distance_sq(target, ansi256_to_rgb(idx as u8));
+        if distance < min_distance {
+            min_distance = distance;
+            closest = idx;
+        }
+    };
+
+    for idx in 0..16 {
+        consider(idx);
+    }
+
+    // The squared distance is separable, so the nearest cube point is the
+    // nearest cube value per channel. Ties go to the lower value.
+    let cube_index = |v: u8| match v {
+        0..=47 => 0,
+        48..=115 => 1,
+        _ => usize::from(v - 36) / 40,
+    };
+    let (r, g, b) = target;
+    consider(16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b));
+
+    // The distance to a gray is a parabola with its minimum at the mean of
+    // the channels, so the nearest gray is one of the two steps around it.
+    let sum = u32::from(r) + u32::from(g) + u32::from(b);
+    let step = (sum.saturating_sub(24) / 30).min(23) as usize;
+    consider(232 + step);
+    if step < 23 {
+        consider(233 + step);
+    }
+
+    closest
+}
+
+fn rgb_distance_sq((r, g, b): (u8, u8, u8), (cr, cg, cb): (u8, u8, u8)) -> u32 {
+    let dr = (i32::from(r) - i32::from(cr)).pow(2) as u32;
+    let dg = (i32::from(g) - i32::from(cg)).pow(2) as u32;
+    let db = (i32::from(b) - i32::from(cb)).pow(2) as u32;
+    dr + dg + db
+}
+
 fn ansi256_to_rgb(idx: u8) -> (u8, u8, u8) {
     if idx < 16 {
         let (r, g, b, _) = ANSI_16_COLORS[idx as usize];
@@ -185,3 +756,47 @@ fn ansi256_to_rgb(idx: u8) -> (u8, u8, u8) {
         (gray_value, gray_value, gray_value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exhaustive_ansi256_rgb(target: (u8, u8, u8)) -> usize {
+        (0..=255u8)
+            .min_by_key(|&idx| rgb_distance_sq(target, ansi256_to_rgb(idx)))
+            .unwrap() as usize
+    }
+
+    #[test]
+    fn closest_ansi256_rgb_matches_exhaustive_search() {
+        // A grid that includes the standard color values and the values
+        // around the cube midpoints
+        let values: Vec<u8> = (0..=255)
+            .step_by(8)
+            .chain([47, 48, 115, 116, 155, 156, 195, 196, 235, 236, 255])
+            .collect();
+        for &r in &values {
+            for &g in &values {
+                for &b in &values {
+                    let rgb = (r, g, b);
+                    assert_eq!(
+                        closest_ansi256_rgb(rgb),
+                        exhaustive_ansi256_rgb(rgb),
+                        "{rgb:?}"
+                    );
+                }
+            }
+        }
+
+        // Grays and near grays, around the gray ramp midpoints
+        for v in 0..=253 {
+            for rgb in [(v, v, v), (v, v, v + 1), (v, v + 1, v + 2)] {
+                assert_eq!(
+                    closest_ansi256_rgb(rgb),
+                    exhaustive_ansi256_rgb(rgb),
+                    "{rgb:?}"
+                );
+            }
+        }
+    }
+}
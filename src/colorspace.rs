@@ -0,0 +1,250 @@
+//! Conversions between sRGB and the other color spaces used for color
+//! matching and manipulation.
+
+use crate::math::{
+    atan2, cbrt, cos, exp, hypot, powf, powi, rem_euclid, round, sin, sqrt,
+};
+
+/// A color in the OKLab color space, as `[L, a, b]`.
+pub(crate) type OkLab = [f32; 3];
+
+/// A color in the CIELAB color space (D65 white point), as `[L, a, b]`.
+pub(crate) type Lab = [f32; 3];
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = f32::from(c) / 255.0;
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        powf((c + 0.055) / 1.055, 2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * powf(c, 1.0 / 2.4) - 0.055
+    };
+    to_u8(c)
+}
+
+pub(crate) fn rgb_to_oklab((r, g, b): (u8, u8, u8)) -> OkLab {
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+
+    let l = 0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b;
+    let m = 0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b;
+    let s = 0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b;
+
+    let (l, m, s) = (cbrt(l), cbrt(m), cbrt(s));
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+/// Converts OKLab to sRGB, clipping colors outside of the sRGB gamut.
+pub(crate) fn oklab_to_rgb([l, a, b]: OkLab) -> (u8, u8, u8) {
+    let l_ = l + 0.396_337_78 * a + 0.215_803_76 * b;
+    let m_ = l - 0.105_561_346 * a - 0.063_854_17 * b;
+    let s_ = l - 0.089_484_18 * a - 1.291_485_5 * b;
+
+    let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+
+    (
+        linear_to_srgb(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+        linear_to_srgb(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+        linear_to_srgb(
+            -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+        ),
+    )
+}
+
+pub(crate) fn rgb_to_lab((r, g, b): (u8, u8, u8)) -> Lab {
+    const EPSILON: f32 = 216.0 / 24389.0;
+    const KAPPA: f32 = 24389.0 / 27.0;
+
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+
+    // Linear sRGB to XYZ, normalized by the D65 reference white
+    let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+    let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+    let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / 1.088_83;
+
+    let f = |t: f32| {
+        if t > EPSILON {
+            cbrt(t)
+        } else {
+            (KAPPA * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Squared euclidean distance, which is what OKLab is designed for.
+pub(crate) fn oklab_distance_sq(a: OkLab, b: OkLab) -> f32 {
+    let (dl, da, db) = (a[0] - b[0], a[1] - b[1], a[2] - b[2]);
+    dl * dl + da * da + db * db
+}
+
+/// The CIEDE2000 color difference between two CIELAB colors.
+pub(crate) fn ciede2000(lab1: Lab, lab2: Lab) -> f32 {
+    use core::f32::consts::PI;
+
+    let [l1, a1, b1] = lab1;
+    let [l2, a2, b2] = lab2;
+
+    let c_bar = (hypot(a1, b1) + hypot(a2, b2)) / 2.0;
+    let c_bar7 = powi(c_bar, 7);
+    let g = 0.5 * (1.0 - sqrt(c_bar7 / (c_bar7 + powi(25.0, 7))));
+
+    let a1p = a1 * (1.0 + g);
+    let a2p = a2 * (1.0 + g);
+    let c1p = hypot(a1p, b1);
+    let c2p = hypot(a2p, b2);
+
+    let hue = |b: f32, a: f32| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            rem_euclid(atan2(b, a), 2.0 * PI)
+        }
+    };
+    let h1p = hue(b1, a1p);
+    let h2p = hue(b2, a2p);
+
+    let dlp = l2 - l1;
+    let dcp = c2p - c1p;
+    let dhp = if c1p * c2p == 0.0 {
+        0.0
+    } else {
+        let dh = h2p - h1p;
+        if dh > PI {
+            dh - 2.0 * PI
+        } else if dh < -PI {
+            dh + 2.0 * PI
+        } else {
+            dh
+        }
+    };
+    let dhp_big = 2.0 * sqrt(c1p * c2p) * sin(dhp / 2.0);
+
+    let lp_bar = (l1 + l2) / 2.0;
+    let cp_bar = (c1p + c2p) / 2.0;
+    let hp_bar = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= PI {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 2.0 * PI {
+        (h1p + h2p + 2.0 * PI) / 2.0
+    } else {
+        (h1p + h2p - 2.0 * PI) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * cos(hp_bar - PI / 6.0)
+        + 0.24 * cos(2.0 * hp_bar)
+        + 0.32 * cos(3.0 * hp_bar + PI / 30.0)
+        - 0.20 * cos(4.0 * hp_bar - 63.0 * PI / 180.0);
+
+    let d_hue = (hp_bar.to_degrees() - 275.0) / 25.0;
+    let d_theta = PI / 6.0 * exp(-(d_hue * d_hue));
+    let cp_bar7 = powi(cp_bar, 7);
+    let r_c = 2.0 * sqrt(cp_bar7 / (cp_bar7 + powi(25.0, 7)));
+    let dl50_sq = (lp_bar - 50.0) * (lp_bar - 50.0);
+    let s_l = 1.0 + (0.015 * dl50_sq) / sqrt(20.0 + dl50_sq);
+    let s_c = 1.0 + 0.045 * cp_bar;
+    let s_h = 1.0 + 0.015 * cp_bar * t;
+    let r_t = -sin(2.0 * d_theta) * r_c;
+
+    let dl = dlp / s_l;
+    let dc = dcp / s_c;
+    let dh = dhp_big / s_h;
+
+    sqrt(dl * dl + dc * dc + dh * dh + r_t * dc * dh)
+}
+
+/// Returns the hue in degrees, the minimum and maximum channel and the
+/// chroma of an sRGB color, which HSL and HSV are both derived from.
+fn hue_and_range((r, g, b): (u8, u8, u8)) -> (f32, f32, f32, f32) {
+    let (r, g, b) = (
+        f32::from(r) / 255.0,
+        f32::from(g) / 255.0,
+        f32::from(b) / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * rem_euclid((g - b) / chroma, 6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+
+    (hue, min, max, chroma)
+}
+
+/// Converts sRGB to HSL, see [`hsl_to_rgb`] for the ranges.
+pub(crate) fn rgb_to_hsl(rgb: (u8, u8, u8)) -> (f32, f32, f32) {
+    let (hue, min, max, chroma) = hue_and_range(rgb);
+    let l = (max + min) / 2.0;
+    let s = if chroma == 0.0 {
+        0.0
+    } else {
+        chroma / (1.0 - (2.0 * l - 1.0).abs())
+    };
+
+    (hue, s, l)
+}
+
+/// Converts sRGB to HSV, see [`hsv_to_rgb`] for the ranges.
+pub(crate) fn rgb_to_hsv(rgb: (u8, u8, u8)) -> (f32, f32, f32) {
+    let (hue, _, max, chroma) = hue_and_range(rgb);
+    let s = if max == 0.0 { 0.0 } else { chroma / max };
+
+    (hue, s, max)
+}
+
+/// Converts HSV to sRGB, with the hue in degrees and saturation and value
+/// in `0.0..=1.0`.
+pub(crate) fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
+    let c = v * s;
+    chroma_to_rgb(h, c, v - c)
+}
+
+/// Converts HSL to sRGB, with the hue in degrees and saturation and
+/// lightness in `0.0..=1.0`.
+pub(crate) fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    chroma_to_rgb(h, c, l - c / 2.0)
+}
+
+/// Builds an sRGB color from a hue, a chroma and the value of the smallest
+/// channel.
+fn chroma_to_rgb(h: f32, c: f32, m: f32) -> (u8, u8, u8) {
+    let h = rem_euclid(h, 360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    (to_u8(r + m), to_u8(g + m), to_u8(b + m))
+}
+
+/// Converts a channel in `0.0..=1.0` to `0..=255`.
+fn to_u8(c: f32) -> u8 {
+    round(c * 255.0).clamp(0.0, 255.0) as u8
+}
@@ -4,6 +4,12 @@
 //!
 //! And get the current color level with [`get_should_colorize()`].
 //!
+//! To change the level for a single thread only, e.g. while rendering a log
+//! file, use [`with_color_level()`] or [`override_color_level()`].
+//!
+//! Whether the terminal has a light or dark background is available as
+//! [`BACKGROUND_DETECTED`], see [`Background`].
+//!
 //! # Example
 //! ```rust
 //! use cnxt::control::{set_should_colorize, ShouldColorize};
@@ -18,18 +24,24 @@
 //! set_should_colorize(ShouldColorize::YesWithAnsi16);
 //!
 //! // Automatically enable or disable colorization based on environment variables and terminal status
+//! # #[cfg(feature = "std")]
 //! set_should_colorize(ShouldColorize::from_env());
 //! ```
 
+use alloc::string::String;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+#[cfg(feature = "std")]
 use std::{
-    env, io,
-    io::IsTerminal,
-    sync::{
-        LazyLock,
-        atomic::{AtomicU8, Ordering},
-    },
+    cell::Cell,
+    env,
+    io::{self, IsTerminal},
+    marker::PhantomData,
+    sync::LazyLock,
+    time::Duration,
 };
 
+use crate::{ColorDistance, CustomColor, colorspace::rgb_to_oklab};
+
 /// The detected color level for the current terminal.
 ///
 /// This is lazily initialized the first time it's accessed and detects
@@ -43,9 +55,29 @@ use std::{
 /// // Get the detected color level
 /// let detected_level = *COLOR_LEVEL_DETECTED;
 /// ```
+#[cfg(feature = "std")]
 pub static COLOR_LEVEL_DETECTED: LazyLock<ColorLevel> =
     LazyLock::new(ColorLevel::detect);
 
+/// The detected background of the current terminal, or `None` if it's
+/// unknown.
+///
+/// This is lazily initialized with [`Background::from_env()`] the first time
+/// it's accessed. The terminal is not queried, use [`Background::detect()`]
+/// for that.
+///
+/// ```rust
+/// use cnxt::control::{BACKGROUND_DETECTED, Background};
+///
+/// let warning_color = match *BACKGROUND_DETECTED {
+///     Some(Background::Light) => "magenta",
+///     _ => "yellow",
+/// };
+/// ```
+#[cfg(feature = "std")]
+pub static BACKGROUND_DETECTED: LazyLock<Option<Background>> =
+    LazyLock::new(Background::from_env);
+
 /// The global setting for whether and how to colorize output.
 ///
 /// When the default feature `terminal-detection` is disabled, this defaults to `YesWithTrueColor`.
@@ -54,18 +86,194 @@ pub static COLOR_LEVEL_DETECTED: LazyLock<ColorLevel> =
 /// [`ShouldColorize`]. It's initialized from the environment using
 /// [`ShouldColorize::from_env()`] but can be changed at runtime with
 /// [`set_should_colorize()`].
-pub static SHOULD_COLORIZE: LazyLock<AtomicU8> = LazyLock::new(|| {
-    AtomicU8::new(
-        #[cfg(feature = "terminal-detection")]
-        {
-            ShouldColorize::from_env() as u8
-        },
-        #[cfg(not(feature = "terminal-detection"))]
-        {
-            ShouldColorize::YesWithTrueColor as u8
-        },
-    )
-});
+///
+/// This is the setting for [`Stream::Stdout`], which is also what the
+/// [`Display`](std::fmt::Display) implementation of
+/// [`ColoredString`](crate::ColoredString) uses.
+#[cfg(feature = "std")]
+pub static SHOULD_COLORIZE: LazyLock<AtomicU8> =
+    LazyLock::new(|| AtomicU8::new(initial_should_colorize(Stream::Stdout)));
+
+/// The global setting for whether and how to colorize output.
+///
+/// Without the `std` feature there is nothing to detect, so this defaults
+/// to `YesWithTrueColor`. It can be changed at runtime with
+/// [`set_should_colorize()`].
+#[cfg(not(feature = "std"))]
+pub static SHOULD_COLORIZE: AtomicU8 =
+    AtomicU8::new(ShouldColorize::YesWithTrueColor as u8);
+
+/// The setting for whether and how to colorize output written to
+/// [`Stream::Stderr`].
+///
+/// It's initialized from the environment using
+/// [`ShouldColorize::from_env_for()`] with stderr, and can be changed at
+/// runtime with [`set_should_colorize_for()`].
+#[cfg(feature = "std")]
+pub static SHOULD_COLORIZE_STDERR: LazyLock<AtomicU8> =
+    LazyLock::new(|| AtomicU8::new(initial_should_colorize(Stream::Stderr)));
+
+/// The setting for whether and how to colorize output written to
+/// [`Stream::Stderr`].
+///
+/// Without the `std` feature this defaults to `YesWithTrueColor`, like
+/// [`SHOULD_COLORIZE`].
+#[cfg(not(feature = "std"))]
+pub static SHOULD_COLORIZE_STDERR: AtomicU8 =
+    AtomicU8::new(ShouldColorize::YesWithTrueColor as u8);
+
+#[cfg(feature = "std")]
+#[cfg_attr(not(feature = "terminal-detection"), allow(unused_variables))]
+fn initial_should_colorize(stream: Stream) -> u8 {
+    #[cfg(feature = "terminal-detection")]
+    {
+        match stream {
+            Stream::Stdout => ShouldColorize::from_env_for(&io::stdout()) as u8,
+            Stream::Stderr => ShouldColorize::from_env_for(&io::stderr()) as u8,
+        }
+    }
+    #[cfg(not(feature = "terminal-detection"))]
+    {
+        ShouldColorize::YesWithTrueColor as u8
+    }
+}
+
+/// The standard output streams that have their own colorization setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    /// The standard output stream.
+    Stdout,
+    /// The standard error stream.
+    Stderr,
+}
+
+impl Stream {
+    fn setting(self) -> &'static AtomicU8 {
+        match self {
+            Self::Stdout => &SHOULD_COLORIZE,
+            Self::Stderr => &SHOULD_COLORIZE_STDERR,
+        }
+    }
+}
+
+static COLOR_DISTANCE: AtomicU8 = AtomicU8::new(ColorDistance::Rgb as u8);
+
+/// Sets how colors are matched when downgrading them to a smaller palette.
+///
+/// Defaults to [`ColorDistance::Rgb`]. Use the `_with` variants of the
+/// fallback methods, like [`Color::fallback_to_ansi256_with`], to pick a
+/// metric for a single conversion.
+///
+/// ```rust
+/// use cnxt::{ColorDistance, control::set_color_distance};
+///
+/// set_color_distance(ColorDistance::OkLab);
+/// ```
+///
+/// [`Color::fallback_to_ansi256_with`]: crate::Color::fallback_to_ansi256_with
+pub fn set_color_distance(distance: ColorDistance) {
+    COLOR_DISTANCE.store(distance as u8, Ordering::Relaxed);
+}
+
+/// Gets how colors are matched when downgrading them to a smaller palette.
+pub fn get_color_distance() -> ColorDistance {
+    match COLOR_DISTANCE.load(Ordering::Relaxed) {
+        1 => ColorDistance::OkLab,
+        2 => ColorDistance::Ciede2000,
+        _ => ColorDistance::Rgb,
+    }
+}
+
+/// The global setting for whether to write hyperlinks.
+///
+/// When the default feature `terminal-detection` is disabled, this defaults
+/// to `false`.
+///
+/// It's initialized with [`hyperlinks_from_env()`] and can be changed at
+/// runtime with [`set_hyperlinks()`].
+#[cfg(feature = "std")]
+pub static HYPERLINKS: LazyLock<AtomicBool> =
+    LazyLock::new(|| AtomicBool::new(initial_hyperlinks()));
+
+/// The global setting for whether to write hyperlinks.
+///
+/// Without the `std` feature this defaults to `false`, and can be changed
+/// at runtime with [`set_hyperlinks()`].
+#[cfg(not(feature = "std"))]
+pub static HYPERLINKS: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "std")]
+fn initial_hyperlinks() -> bool {
+    #[cfg(feature = "terminal-detection")]
+    {
+        hyperlinks_from_env()
+    }
+    #[cfg(not(feature = "terminal-detection"))]
+    {
+        false
+    }
+}
+
+/// Sets whether [`ColoredString`](crate::ColoredString)s write their links
+/// as OSC 8 hyperlinks when displayed.
+///
+/// Links are never written when colors are disabled, regardless of this
+/// setting. Rendering at an explicit level, e.g. with
+/// [`ColoredString::render`](crate::ColoredString::render), doesn't read
+/// this setting.
+///
+/// Default value is generated by [`hyperlinks_from_env()`].
+pub fn set_hyperlinks(enabled: bool) {
+    HYPERLINKS.store(enabled, Ordering::Relaxed);
+}
+
+/// Gets whether [`ColoredString`](crate::ColoredString)s write their links
+/// as OSC 8 hyperlinks.
+pub fn get_hyperlinks() -> bool {
+    HYPERLINKS.load(Ordering::Relaxed)
+}
+
+/// Determines whether the terminal is known to support hyperlinks, based on
+/// environment variables.
+///
+/// `FORCE_HYPERLINK` takes precedence: any value other than `0` enables
+/// hyperlinks and `0` disables them. Otherwise hyperlinks are enabled for
+/// terminals known to support them, such as iTerm2, WezTerm, Ghostty,
+/// VS Code, Windows Terminal, Konsole, kitty, foot and VTE based terminals.
+#[must_use]
+#[cfg(feature = "std")]
+pub fn hyperlinks_from_env() -> bool {
+    if let Ok(force) = env::var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+
+    if let Ok(program) = env::var("TERM_PROGRAM")
+        && TERM_PROGRAMS
+            .iter()
+            .any(|&(name, _, hyperlinks)| name == program && hyperlinks)
+    {
+        return true;
+    }
+
+    // VTE supports hyperlinks since 0.50
+    if env::var("VTE_VERSION")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .is_some_and(|v| v >= 5000)
+    {
+        return true;
+    }
+
+    if env::var_os("WT_SESSION").is_some()
+        || env::var_os("KONSOLE_VERSION").is_some()
+    {
+        return true;
+    }
+
+    env::var("TERM").is_ok_and(|term| {
+        matches!(term.as_str(), "xterm-kitty" | "foot" | "foot-extra")
+    })
+}
 
 /// Sets a flag to the console to use a virtual terminal environment.
 ///
@@ -104,24 +312,136 @@ pub fn set_virtual_terminal(use_virtual: bool) {
 
 /// Sets the color level to use for the terminal.
 ///
+/// This applies to both stdout and stderr, use [`set_should_colorize_for()`]
+/// to configure a single stream.
+///
 /// Default value is generated by [`ShouldColorize::from_env()`].
 pub fn set_should_colorize(should_colorize: ShouldColorize) {
-    SHOULD_COLORIZE.store(should_colorize as u8, Ordering::Relaxed);
+    set_should_colorize_for(Stream::Stdout, should_colorize);
+    set_should_colorize_for(Stream::Stderr, should_colorize);
 }
 
 /// Gets the current color level to use for the terminal.
 pub fn get_should_colorize() -> ShouldColorize {
-    SHOULD_COLORIZE.load(Ordering::Relaxed).into()
+    get_should_colorize_for(Stream::Stdout)
+}
+
+/// Sets the color level to use for the given stream only.
+///
+/// Default value is generated by [`ShouldColorize::from_env_for()`] with the
+/// matching standard stream.
+pub fn set_should_colorize_for(
+    stream: Stream,
+    should_colorize: ShouldColorize,
+) {
+    stream
+        .setting()
+        .store(should_colorize as u8, Ordering::Relaxed);
 }
 
+/// Gets the current color level to use for the given stream.
+pub fn get_should_colorize_for(stream: Stream) -> ShouldColorize {
+    stream.setting().load(Ordering::Relaxed).into()
+}
+
+/// Gets the color level that is used when rendering for stdout.
 pub fn get_current_color_level() -> ColorLevel {
-    match get_should_colorize() {
+    get_color_level_for(Stream::Stdout)
+}
+
+/// Gets the color level that is used when rendering for the given stream.
+///
+/// A scoped override set on the current thread with [`with_color_level()`]
+/// or [`override_color_level()`] takes precedence over the stream setting.
+pub fn get_color_level_for(stream: Stream) -> ColorLevel {
+    #[cfg(feature = "std")]
+    if let Some(level) = COLOR_LEVEL_OVERRIDE.get() {
+        return level;
+    }
+
+    match get_should_colorize_for(stream) {
         ShouldColorize::No => ColorLevel::None,
+        #[cfg(feature = "std")]
         ShouldColorize::Yes => *COLOR_LEVEL_DETECTED,
+        // Without std there is nothing to detect
+        #[cfg(not(feature = "std"))]
+        ShouldColorize::Yes => ColorLevel::TrueColor,
         level => level.into(),
     }
 }
 
+#[cfg(feature = "std")]
+thread_local! {
+    static COLOR_LEVEL_OVERRIDE: Cell<Option<ColorLevel>> =
+        const { Cell::new(None) };
+}
+
+/// Overrides the color level on the current thread until the returned guard
+/// is dropped.
+///
+/// The override takes precedence over [`SHOULD_COLORIZE`] and the per-stream
+/// settings, and does not affect other threads. Dropping the guard restores
+/// whatever level was in effect before, so overrides can be nested.
+///
+/// ```rust
+/// use cnxt::{
+///     Colorize as _,
+///     control::{ColorLevel, override_color_level},
+/// };
+///
+/// let _guard = override_color_level(ColorLevel::None);
+/// assert_eq!("plain".red().to_string(), "plain");
+/// ```
+#[cfg(feature = "std")]
+pub fn override_color_level(level: ColorLevel) -> ColorLevelGuard {
+    ColorLevelGuard {
+        previous: COLOR_LEVEL_OVERRIDE.replace(Some(level)),
+        _not_send: PhantomData,
+    }
+}
+
+/// Runs `f` with the color level overridden on the current thread.
+///
+/// See [`override_color_level()`] for details.
+///
+/// ```rust
+/// use cnxt::{
+///     Colorize as _,
+///     control::{ColorLevel, with_color_level},
+/// };
+///
+/// let log_line = with_color_level(ColorLevel::None, || "ok".green().to_string());
+/// assert_eq!(log_line, "ok");
+///
+/// let colored = with_color_level(ColorLevel::Ansi16, || "ok".green().to_string());
+/// assert_eq!(colored, "\x1B[32mok\x1B[0m");
+/// ```
+#[cfg(feature = "std")]
+pub fn with_color_level<R>(level: ColorLevel, f: impl FnOnce() -> R) -> R {
+    let _guard = override_color_level(level);
+    f()
+}
+
+/// Guard returned by [`override_color_level()`].
+///
+/// Restores the previous thread-local color level when dropped.
+#[must_use = "the override is removed as soon as the guard is dropped"]
+#[derive(Debug)]
+#[cfg(feature = "std")]
+pub struct ColorLevelGuard {
+    previous: Option<ColorLevel>,
+    // The override lives in a thread local, so the guard must stay on the
+    // thread that created it.
+    _not_send: PhantomData<*const ()>,
+}
+
+#[cfg(feature = "std")]
+impl Drop for ColorLevelGuard {
+    fn drop(&mut self) {
+        COLOR_LEVEL_OVERRIDE.set(self.previous);
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// The color level to use for the terminal.
 /// Determines the color depth to use for the terminal.
@@ -136,23 +456,129 @@ pub enum ColorLevel {
     TrueColor,
 }
 
+/// Color levels of terminals, by their `TERM_PROGRAM`, and whether they
+/// support hyperlinks.
+const TERM_PROGRAMS: [(&str, ColorLevel, bool); 6] = [
+    ("iTerm.app", ColorLevel::TrueColor, true),
+    ("vscode", ColorLevel::TrueColor, true),
+    ("WezTerm", ColorLevel::TrueColor, true),
+    ("Hyper", ColorLevel::TrueColor, false),
+    ("ghostty", ColorLevel::TrueColor, true),
+    ("Apple_Terminal", ColorLevel::Ansi256, false),
+];
+
+/// Color levels of CI services, by an environment variable they set.
+///
+/// Other services that set `CI` get [`ColorLevel::Ansi16`].
+const CI_VENDORS: [(&str, ColorLevel); 8] = [
+    ("GITHUB_ACTIONS", ColorLevel::TrueColor),
+    ("GITLAB_CI", ColorLevel::TrueColor),
+    ("TRAVIS", ColorLevel::Ansi16),
+    ("CIRCLECI", ColorLevel::Ansi16),
+    ("APPVEYOR", ColorLevel::Ansi16),
+    ("BUILDKITE", ColorLevel::Ansi16),
+    ("DRONE", ColorLevel::Ansi16),
+    ("TEAMCITY_VERSION", ColorLevel::Ansi16),
+];
+
 impl ColorLevel {
+    #[cfg(feature = "std")]
     fn detect() -> Self {
+        Self::detect_with(|name| env::var(name).ok())
+    }
+
+    /// Detects the color level from environment variables looked up with
+    /// `var`, the same way [`COLOR_LEVEL_DETECTED`] is initialized.
+    ///
+    /// The checks, in order:
+    /// 1. `TERM=dumb` has no colors.
+    /// 2. `COLORTERM=truecolor` or `24bit` has true color.
+    /// 3. CI services: GitHub Actions and GitLab CI have true color, others
+    ///    that set `CI` have 16 colors.
+    /// 4. Terminal multiplexers: tmux (`TMUX`) has 256 colors, GNU screen
+    ///    (`STY`) has 256 colors if `TERM` says so and 16 otherwise.
+    /// 5. Windows Terminal (`WT_SESSION`) and the terminals known by
+    ///    `TERM_PROGRAM`, such as iTerm2, VS Code, WezTerm, Hyper and
+    ///    Apple Terminal.
+    /// 6. The Windows version, on Windows.
+    /// 7. The terminfo entry of `TERM`, with the `terminfo` feature.
+    /// 8. A `TERM` ending in `256color` has 256 colors, anything else 16.
+    ///
+    /// ```rust
+    /// use cnxt::control::ColorLevel;
+    ///
+    /// let detect = |vars: &[(&str, &str)]| {
+    ///     ColorLevel::detect_with(|name| {
+    ///         vars.iter()
+    ///             .find(|(key, _)| *key == name)
+    ///             .map(|(_, value)| value.to_string())
+    ///     })
+    /// };
+    ///
+    /// assert_eq!(detect(&[("TERM", "dumb")]), ColorLevel::None);
+    /// assert_eq!(
+    ///     detect(&[("CI", "true"), ("GITHUB_ACTIONS", "true")]),
+    ///     ColorLevel::TrueColor
+    /// );
+    /// assert_eq!(
+    ///     detect(&[("CI", "true"), ("TRAVIS", "true")]),
+    ///     ColorLevel::Ansi16
+    /// );
+    /// assert_eq!(
+    ///     detect(&[("TMUX", "/tmp/tmux-1000/default,42,0")]),
+    ///     ColorLevel::Ansi256
+    /// );
+    /// assert_eq!(
+    ///     detect(&[("TERM_PROGRAM", "Apple_Terminal")]),
+    ///     ColorLevel::Ansi256
+    /// );
+    /// ```
+    pub fn detect_with(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+
+        if term == "dumb" {
+            return Self::None;
+        }
+
         // Check for 24-bit color support via COLORTERM
-        if env::var("COLORTERM")
-            .is_ok_and(|v| matches!(v.as_str(), "truecolor" | "24bit"))
+        if var("COLORTERM")
+            .is_some_and(|v| matches!(v.as_str(), "truecolor" | "24bit"))
         {
             return Self::TrueColor;
         }
 
+        if let Some(&(_, level)) =
+            CI_VENDORS.iter().find(|(name, _)| var(name).is_some())
+        {
+            return level;
+        }
+        if var("CI").is_some() {
+            return Self::Ansi16;
+        }
+
+        // Multiplexers come before the terminal, whose variables they
+        // inherit but whose capabilities they don't necessarily pass on
+        if var("TMUX").is_some() {
+            return Self::Ansi256;
+        }
+        if var("STY").is_some() {
+            return if term.ends_with("256color") {
+                Self::Ansi256
+            } else {
+                Self::Ansi16
+            };
+        }
+
         // Detect Windows Terminal in Windows and WSL
-        if env::var_os("WT_SESSION").is_some() {
+        if var("WT_SESSION").is_some() {
             return Self::TrueColor;
         }
 
-        // Detect CI environments, which typically have limited color support
-        if env::var_os("CI").is_some() {
-            return Self::Ansi256;
+        if let Some(program) = var("TERM_PROGRAM")
+            && let Some(&(_, level, _)) =
+                TERM_PROGRAMS.iter().find(|(name, ..)| *name == program)
+        {
+            return level;
         }
 
         // Windows version-specific checks
@@ -172,13 +598,18 @@ impl ColorLevel {
             }
         }
 
-        // Check TERM for 256-color indication
-        if let Some(term) =
-            env::var_os("TERM").and_then(|term| term.into_string().ok())
+        // Ask the terminfo database about TERM
+        #[cfg(feature = "terminfo")]
+        if let Some(level) = crate::terminfo::TermInfo::load(&term)
+            .ok()
+            .and_then(|info| info.color_level())
         {
-            if term.ends_with("-256color") || term.ends_with("256") {
-                return Self::Ansi256;
-            }
+            return level;
+        }
+
+        // Check TERM for 256-color indication
+        if term.ends_with("256color") || term.ends_with("256") {
+            return Self::Ansi256;
         }
 
         // Fallback to basic ANSI colors
@@ -186,6 +617,237 @@ impl ColorLevel {
     }
 }
 
+/// Whether the terminal has a light or a dark background.
+///
+/// Useful to pick colors that stay readable, e.g. yellow text is hard to
+/// read on a white background.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    /// A light background, such as white.
+    Light,
+    /// A dark background, such as black.
+    Dark,
+}
+
+impl Background {
+    /// Determines the background from the `COLORFGBG` environment variable,
+    /// which is set by rxvt, Konsole and some other terminals.
+    ///
+    /// Returns `None` if it's not set or not understood, see
+    /// [`Background::from_colorfgbg()`].
+    #[must_use]
+    #[cfg(feature = "std")]
+    pub fn from_env() -> Option<Self> {
+        env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| Self::from_colorfgbg(&value))
+    }
+
+    /// Parses a `COLORFGBG` value, such as `15;0`.
+    ///
+    /// The last field is the ANSI color index of the background. The
+    /// standard colors other than white, as well as bright black, are
+    /// dark.
+    ///
+    /// ```rust
+    /// use cnxt::control::Background;
+    ///
+    /// assert_eq!(Background::from_colorfgbg("15;0"), Some(Background::Dark));
+    /// assert_eq!(
+    ///     Background::from_colorfgbg("0;default;15"),
+    ///     Some(Background::Light)
+    /// );
+    /// assert_eq!(Background::from_colorfgbg("default;default"), None);
+    /// ```
+    #[must_use]
+    pub fn from_colorfgbg(value: &str) -> Option<Self> {
+        match value.rsplit(';').next()?.parse::<u8>().ok()? {
+            0..=6 | 8 => Some(Self::Dark),
+            7 | 9..=15 => Some(Self::Light),
+            _ => None,
+        }
+    }
+
+    /// Returns the background for a background color, based on its
+    /// perceived lightness.
+    ///
+    /// ```rust
+    /// use cnxt::control::Background;
+    ///
+    /// assert_eq!(Background::from_color((40, 42, 54)), Background::Dark);
+    /// assert_eq!(Background::from_color((253, 246, 227)), Background::Light);
+    /// ```
+    #[must_use]
+    pub fn from_color(color: impl Into<CustomColor>) -> Self {
+        let [lightness, ..] = rgb_to_oklab(color.into().into());
+        if lightness > 0.6 {
+            Self::Light
+        } else {
+            Self::Dark
+        }
+    }
+
+    /// Asks the terminal for its background color with an OSC 11 query and
+    /// waits at most `timeout` for the reply.
+    ///
+    /// Returns `None` if there is no controlling terminal, the process runs
+    /// in the background, or the terminal does not reply in time or does
+    /// not support the query. The query is only supported on Unix.
+    ///
+    /// This writes to and reads from the terminal, so it should not be
+    /// called while another thread reads input.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use cnxt::control::Background;
+    ///
+    /// let background = Background::query(Duration::from_millis(100));
+    /// ```
+    #[must_use]
+    #[cfg(feature = "std")]
+    pub fn query(timeout: Duration) -> Option<Self> {
+        query_background_color(timeout).map(Self::from_color)
+    }
+
+    /// Determines the background from the environment with
+    /// [`Background::from_env()`], and falls back to
+    /// [`Background::query()`] with `timeout`.
+    #[must_use]
+    #[cfg(feature = "std")]
+    pub fn detect(timeout: Duration) -> Option<Self> {
+        Self::from_env().or_else(|| Self::query(timeout))
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+fn query_background_color(timeout: Duration) -> Option<CustomColor> {
+    use std::{fs::OpenOptions, mem::MaybeUninit, os::fd::AsRawFd as _};
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+
+    // A background process that touches the terminal settings is stopped
+    // with SIGTTOU, and its query could steal input from the foreground job
+    if unsafe { libc::tcgetpgrp(fd) != libc::getpgrp() } {
+        return None;
+    }
+
+    // Read the reply as it arrives, without echoing it
+    let original = unsafe {
+        let mut termios = MaybeUninit::uninit();
+        if libc::tcgetattr(fd, termios.as_mut_ptr()) != 0 {
+            return None;
+        }
+        termios.assume_init()
+    };
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+
+    let reply = read_osc11_reply(&mut tty, timeout);
+
+    // Drop what is left of a late reply, so it isn't read as user input
+    unsafe {
+        libc::tcflush(fd, libc::TCIFLUSH);
+        libc::tcsetattr(fd, libc::TCSANOW, &original);
+    }
+
+    parse_osc11_reply(&reply?)
+}
+
+#[cfg(all(feature = "std", not(unix)))]
+fn query_background_color(_timeout: Duration) -> Option<CustomColor> {
+    None
+}
+
+/// Sends the OSC 11 query followed by a primary device attributes query,
+/// and reads until the reply to the latter arrives. Every terminal answers
+/// device attributes, so terminals without OSC 11 support don't make us
+/// wait for the whole timeout.
+#[cfg(all(feature = "std", unix))]
+fn read_osc11_reply(
+    tty: &mut std::fs::File,
+    timeout: Duration,
+) -> Option<Vec<u8>> {
+    use std::{
+        io::{Read as _, Write as _},
+        os::fd::AsRawFd as _,
+        time::Instant,
+    };
+
+    tty.write_all(b"\x1B]11;?\x1B\\\x1B[c").ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+    let mut buf = [0; 64];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut pollfd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let millis = remaining.as_millis().min(i32::MAX as u128) as i32;
+        if unsafe { libc::poll(&mut pollfd, 1, millis) } <= 0 {
+            break;
+        }
+
+        match tty.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => reply.extend_from_slice(&buf[..n]),
+        }
+
+        // The device attributes reply is `ESC [ ? ... c`
+        if let Some(start) = reply.windows(3).position(|w| w == b"\x1B[?")
+            && reply[start..].contains(&b'c')
+        {
+            break;
+        }
+    }
+
+    Some(reply)
+}
+
+/// Parses the color from an OSC 11 reply like
+/// `ESC ] 11 ; rgb:ffff/ffff/ffff ESC \`.
+#[cfg(feature = "std")]
+fn parse_osc11_reply(reply: &[u8]) -> Option<CustomColor> {
+    let reply = String::from_utf8_lossy(reply);
+    let (_, rest) = reply.split_once("\x1B]11;")?;
+    let end = rest.find(['\x07', '\x1B']).unwrap_or(rest.len());
+    let spec = &rest[..end];
+    let channels = spec
+        .strip_prefix("rgb:")
+        .or_else(|| spec.strip_prefix("rgba:"))?;
+
+    // Each channel has one to four hex digits
+    let mut channels = channels.split('/').map(|channel| {
+        let value = u32::from_str_radix(channel, 16).ok()?;
+        let max = match channel.len() {
+            len @ 1..=4 => (1 << (4 * len)) - 1,
+            _ => return None,
+        };
+        Some((value * 255 / max) as u8)
+    });
+
+    Some(CustomColor::new(
+        channels.next()??,
+        channels.next()??,
+        channels.next()??,
+    ))
+}
+
 /// Whether and how to colorize the output.
 #[repr(u8)]
 #[derive(Clone, Copy, Debug)]
@@ -206,12 +868,39 @@ impl ShouldColorize {
     /// Determines if colorization should be applied based on environment variables and terminal status.
     ///
     /// Priority order for environment variables:
-    /// 1. `CLICOLOR_FORCE` (force enable colorization)
-    /// 2. `NO_COLOR` (force disable colorization)
-    /// 3. `CLICOLOR` (enable colorization if set, depending on tty)
-    /// 4. If none of the above, use the terminal status (enabled if stdout is a tty)
+    /// 1. `FORCE_COLOR` (force a color level, or disable colorization, see
+    ///    [`ShouldColorize::from_force_color()`])
+    /// 2. `CLICOLOR_FORCE` (force enable colorization)
+    /// 3. `NO_COLOR` (force disable colorization)
+    /// 4. `CLICOLOR` (enable colorization if set, depending on tty)
+    /// 5. If none of the above, use the terminal status (enabled if stdout is a tty)
     #[must_use]
+    #[cfg(feature = "std")]
     pub fn from_env() -> Self {
+        Self::from_env_for(&io::stdout())
+    }
+
+    /// Same as [`ShouldColorize::from_env()`], but checks whether `stream`
+    /// is a tty instead of stdout.
+    ///
+    /// Any handle implementing [`IsTerminal`] can be passed, a raw
+    /// descriptor can be checked through `handle.as_fd()`.
+    ///
+    /// ```rust
+    /// use cnxt::control::ShouldColorize;
+    ///
+    /// let for_stderr = ShouldColorize::from_env_for(&std::io::stderr());
+    /// ```
+    #[must_use]
+    #[cfg(feature = "std")]
+    pub fn from_env_for<T: IsTerminal>(stream: &T) -> Self {
+        if let Some(should_colorize) = env::var("FORCE_COLOR")
+            .ok()
+            .and_then(|v| Self::from_force_color(&v))
+        {
+            return should_colorize;
+        }
+
         if env::var("CLICOLOR_FORCE").is_ok_and(|v| v != "0") {
             return Self::Yes;
         }
@@ -224,12 +913,53 @@ impl ShouldColorize {
             return Self::Yes;
         }
 
-        if io::stdout().is_terminal() {
+        if stream.is_terminal() {
             Self::Yes
         } else {
             Self::No
         }
     }
+
+    /// Parses a `FORCE_COLOR` value, as used by Node.js tools like chalk.
+    ///
+    /// `0` and `false` disable colorization, and `1`, `2` and `3` force 16
+    /// colors, 256 colors and true color. `true` and an empty value are the
+    /// same as `1`, and levels above 3 are the same as `3`. Returns `None`
+    /// for any other value, which is ignored by
+    /// [`ShouldColorize::from_env()`].
+    ///
+    /// ```rust
+    /// use cnxt::control::ShouldColorize;
+    ///
+    /// assert!(matches!(
+    ///     ShouldColorize::from_force_color("0"),
+    ///     Some(ShouldColorize::No)
+    /// ));
+    /// assert!(matches!(
+    ///     ShouldColorize::from_force_color("3"),
+    ///     Some(ShouldColorize::YesWithTrueColor)
+    /// ));
+    /// assert!(matches!(
+    ///     ShouldColorize::from_force_color("true"),
+    ///     Some(ShouldColorize::YesWithAnsi16)
+    /// ));
+    /// assert!(ShouldColorize::from_force_color("yes please").is_none());
+    /// ```
+    #[must_use]
+    pub fn from_force_color(value: &str) -> Option<Self> {
+        let level = match value {
+            "false" => 0,
+            "true" | "" => 1,
+            value => value.parse::<u32>().ok()?,
+        };
+
+        Some(match level {
+            0 => Self::No,
+            1 => Self::YesWithAnsi16,
+            2 => Self::YesWithAnsi256,
+            _ => Self::YesWithTrueColor,
+        })
+    }
 }
 
 impl From<u8> for ShouldColorize {
@@ -1,3 +1,13 @@
+use crate::{
+    Color,
+    color::parse_hex,
+    colorspace::{
+        hsl_to_rgb, hsv_to_rgb, oklab_to_rgb, rgb_to_hsl, rgb_to_hsv,
+        rgb_to_oklab,
+    },
+    math,
+};
+
 /// Custom color structure, it will generate a true color in the result
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct CustomColor {
@@ -16,6 +26,171 @@ impl CustomColor {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Self { r, g, b }
     }
+
+    /// Creates a custom color from a `#rgb` or `#rrggbb` hex code in a
+    /// `const` context.
+    ///
+    /// See [`Color::from_hex_const`] for the accepted codes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hex` is not a valid hex code.
+    ///
+    /// ```rust
+    /// use cnxt::CustomColor;
+    ///
+    /// const PALETTE: [CustomColor; 2] = [
+    ///     CustomColor::from_hex_const("#1e1e2e"),
+    ///     CustomColor::from_hex_const("f38ba8"),
+    /// ];
+    /// assert_eq!(PALETTE[1], CustomColor::new(243, 139, 168));
+    /// ```
+    #[must_use]
+    pub const fn from_hex_const(hex: &str) -> Self {
+        match parse_hex(hex) {
+            Some(Color::TrueColor { r, g, b }) => Self { r, g, b },
+            _ => panic!("invalid hex color"),
+        }
+    }
+}
+
+/// Conversions to and from other color spaces.
+///
+/// Hues are in degrees and wrap around, all other components are in
+/// `0.0..=1.0` unless noted otherwise. Converting back to RGB rounds to the
+/// nearest channel value, and colors outside of the sRGB gamut are clipped.
+impl CustomColor {
+    /// Creates a color from hue, saturation and lightness.
+    ///
+    /// ```rust
+    /// use cnxt::CustomColor;
+    ///
+    /// assert_eq!(CustomColor::from_hsl(120.0, 1.0, 0.25), CustomColor::new(0, 128, 0));
+    /// ```
+    #[must_use]
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Self {
+        hsl_to_rgb(h, s.clamp(0.0, 1.0), l.clamp(0.0, 1.0)).into()
+    }
+
+    /// Returns the hue, saturation and lightness of this color.
+    #[must_use]
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        rgb_to_hsl(self.into())
+    }
+
+    /// Creates a color from hue, saturation and value.
+    #[must_use]
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        hsv_to_rgb(h, s.clamp(0.0, 1.0), v.clamp(0.0, 1.0)).into()
+    }
+
+    /// Returns the hue, saturation and value of this color.
+    #[must_use]
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        rgb_to_hsv(self.into())
+    }
+
+    /// Creates a color from OKLCH lightness, chroma and hue.
+    ///
+    /// The chroma is unbounded, but colors in sRGB stay below about `0.33`.
+    #[must_use]
+    pub fn from_oklch(l: f32, c: f32, h: f32) -> Self {
+        let (sin, cos) = math::sin_cos(h.to_radians());
+        oklab_to_rgb([l.clamp(0.0, 1.0), c.max(0.0) * cos, c.max(0.0) * sin])
+            .into()
+    }
+
+    /// Returns the OKLCH lightness, chroma and hue of this color.
+    ///
+    /// OKLCH is perceptually uniform, so changing the lightness or hue there
+    /// keeps the other properties looking the same.
+    #[must_use]
+    pub fn to_oklch(self) -> (f32, f32, f32) {
+        let [l, a, b] = rgb_to_oklab(self.into());
+        let hue = math::atan2(b, a).to_degrees();
+        (l, math::hypot(a, b), math::rem_euclid(hue, 360.0))
+    }
+}
+
+/// Deriving new colors.
+///
+/// Lightness and saturation are adjusted in HSL by an absolute amount, so
+/// `lighten(0.1)` raises the lightness by 10 percentage points.
+///
+/// ```rust
+/// use cnxt::CustomColor;
+///
+/// let base = CustomColor::new(52, 101, 164);
+/// let hover = base.lighten(0.1);
+/// let muted = base.desaturate(0.3);
+/// let accent = base.complement();
+/// ```
+impl CustomColor {
+    /// Makes this color lighter by `amount`.
+    #[must_use]
+    pub fn lighten(self, amount: f32) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s, l + amount)
+    }
+
+    /// Makes this color darker by `amount`.
+    #[must_use]
+    pub fn darken(self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Makes this color more saturated by `amount`.
+    #[must_use]
+    pub fn saturate(self, amount: f32) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s + amount, l)
+    }
+
+    /// Makes this color less saturated by `amount`.
+    #[must_use]
+    pub fn desaturate(self, amount: f32) -> Self {
+        self.saturate(-amount)
+    }
+
+    /// Mixes this color with `other` in RGB.
+    ///
+    /// `t` is clamped to `0.0..=1.0`, where `0.0` returns this color and
+    /// `1.0` returns `other`.
+    ///
+    /// ```rust
+    /// use cnxt::CustomColor;
+    ///
+    /// let black = CustomColor::new(0, 0, 0);
+    /// let white = CustomColor::new(255, 255, 255);
+    /// assert_eq!(black.mix(white, 0.5), CustomColor::new(128, 128, 128));
+    /// ```
+    #[must_use]
+    pub fn mix(self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| {
+            math::round(f32::from(a) + (f32::from(b) - f32::from(a)) * t) as u8
+        };
+
+        Self::new(
+            lerp(self.r, other.r),
+            lerp(self.g, other.g),
+            lerp(self.b, other.b),
+        )
+    }
+
+    /// Returns the color on the opposite side of the color wheel.
+    #[must_use]
+    pub fn complement(self) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h + 180.0, s, l)
+    }
+
+    /// Returns this color with its HSL hue replaced by `hue`, in degrees.
+    #[must_use]
+    pub fn with_hue(self, hue: f32) -> Self {
+        let (_, s, l) = self.to_hsl();
+        Self::from_hsl(hue, s, l)
+    }
 }
 
 impl From<(u8, u8, u8)> for CustomColor {
@@ -23,3 +198,41 @@ impl From<(u8, u8, u8)> for CustomColor {
         Self::new(r, g, b)
     }
 }
+
+impl From<CustomColor> for (u8, u8, u8) {
+    fn from(color: CustomColor) -> Self {
+        (color.r, color.g, color.b)
+    }
+}
+
+impl From<CustomColor> for Color {
+    fn from(color: CustomColor) -> Self {
+        Self::TrueColor {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+        }
+    }
+}
+
+/// Serialized as a `#rrggbb` hex code.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CustomColor {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Color::from(*self))
+    }
+}
+
+/// Deserialized from any string [`Color`] parses, usually a hex code.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CustomColor {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let color = Color::deserialize(deserializer)?;
+        Ok(color.to_rgb().into())
+    }
+}
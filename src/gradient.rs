@@ -0,0 +1,212 @@
+//! Text colored with a gradient across its characters.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use unicode_segmentation::UnicodeSegmentation as _;
+
+use crate::{
+    Color, ColoredString, CustomColor, StyledText,
+    colorspace::{hsl_to_rgb, oklab_to_rgb, rgb_to_hsl, rgb_to_oklab},
+    control::{self, ColorLevel},
+    math,
+};
+
+/// The color space in which a [`Gradient`] blends between its stops.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Blend the red, green and blue channels. Simple, but the middle of a
+    /// gradient between complementary colors turns gray.
+    #[default]
+    Rgb,
+    /// Blend in HSL, going the short way around the hue circle. Keeps the
+    /// gradient saturated, like a rainbow.
+    Hsl,
+    /// Blend in the perceptually uniform OKLab color space, which gives the
+    /// most even looking transitions.
+    OkLab,
+}
+
+impl Interpolation {
+    fn blend(self, from: CustomColor, to: CustomColor, t: f32) -> CustomColor {
+        match self {
+            Self::Rgb => from.mix(to, t),
+            Self::Hsl => {
+                let (h1, s1, l1) = rgb_to_hsl(from.into());
+                let (h2, s2, l2) = rgb_to_hsl(to.into());
+                let dh = math::rem_euclid(h2 - h1 + 540.0, 360.0) - 180.0;
+                hsl_to_rgb(h1 + dh * t, lerp(s1, s2, t), lerp(l1, l2, t)).into()
+            }
+            Self::OkLab => {
+                let [l1, a1, b1] = rgb_to_oklab(from.into());
+                let [l2, a2, b2] = rgb_to_oklab(to.into());
+                oklab_to_rgb([
+                    lerp(l1, l2, t),
+                    lerp(a1, a2, t),
+                    lerp(b1, b2, t),
+                ])
+                .into()
+            }
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Text whose foreground and/or background color changes gradually from
+/// one grapheme to the next.
+///
+/// Created by [`Colorize::gradient`] and [`Colorize::on_gradient`]. The
+/// stops are spread evenly over the text, and each grapheme gets the color
+/// interpolated at its position. Colors are downgraded like any other
+/// `TrueColor` on terminals with fewer colors.
+///
+/// ```rust
+/// use cnxt::{Colorize as _, Interpolation};
+///
+/// let banner = "Colored Next"
+///     .bold()
+///     .gradient([(243, 139, 168), (137, 180, 250)])
+///     .interpolation(Interpolation::OkLab);
+/// println!("{banner}");
+/// ```
+///
+/// [`Colorize::gradient`]: crate::Colorize::gradient
+/// [`Colorize::on_gradient`]: crate::Colorize::on_gradient
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gradient<'a> {
+    base: ColoredString<'a>,
+    fg_stops: Vec<CustomColor>,
+    bg_stops: Vec<CustomColor>,
+    interpolation: Interpolation,
+}
+
+impl<'a> Gradient<'a> {
+    /// Creates a gradient over `base` without any stops. Colors and style of
+    /// `base` apply wherever no gradient is set.
+    #[must_use]
+    pub fn new(base: impl Into<ColoredString<'a>>) -> Self {
+        Self {
+            base: base.into(),
+            fg_stops: Vec::new(),
+            bg_stops: Vec::new(),
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    /// Sets the stops of the foreground gradient.
+    #[must_use]
+    pub fn gradient<C: Into<CustomColor>>(
+        mut self,
+        stops: impl IntoIterator<Item = C>,
+    ) -> Self {
+        self.fg_stops = stops.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the stops of the background gradient.
+    #[must_use]
+    pub fn on_gradient<C: Into<CustomColor>>(
+        mut self,
+        stops: impl IntoIterator<Item = C>,
+    ) -> Self {
+        self.bg_stops = stops.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the color space to interpolate in.
+    #[must_use]
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Splits the text into one [`ColoredString`] per grapheme, each with
+    /// its interpolated colors.
+    #[must_use]
+    pub fn to_spans(&self) -> Vec<ColoredString<'_>> {
+        let graphemes: Vec<&str> = self.base.input.graphemes(true).collect();
+        let last = graphemes.len().saturating_sub(1).max(1) as f32;
+
+        graphemes
+            .iter()
+            .enumerate()
+            .map(|(idx, &grapheme)| {
+                let t = idx as f32 / last;
+                ColoredString {
+                    input: grapheme.into(),
+                    fgcolor: self
+                        .color_at(&self.fg_stops, t)
+                        .or(self.base.fgcolor),
+                    bgcolor: self
+                        .color_at(&self.bg_stops, t)
+                        .or(self.base.bgcolor),
+                    style: self.base.style,
+                    underline_color: self.base.underline_color,
+                    link: self.base.link.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the color at position `t` in `0.0..=1.0` of a gradient
+    /// through `stops`.
+    fn color_at(&self, stops: &[CustomColor], t: f32) -> Option<Color> {
+        let color = match stops {
+            [] => return None,
+            [only] => *only,
+            stops => {
+                let position = t * (stops.len() - 1) as f32;
+                let idx = (position as usize).min(stops.len() - 2);
+                self.interpolation.blend(
+                    stops[idx],
+                    stops[idx + 1],
+                    position - idx as f32,
+                )
+            }
+        };
+
+        Some(color.into())
+    }
+
+    /// Renders this gradient at the given color level.
+    ///
+    /// See [`ColoredString::render`] for details. Adjacent graphemes are
+    /// rendered as a [`StyledText`], so only the colors that change between
+    /// them are written.
+    ///
+    /// ```rust
+    /// use cnxt::{Colorize as _, control::ColorLevel};
+    ///
+    /// let text = "abc".gradient([(255, 0, 0), (0, 0, 255)]);
+    /// assert_eq!(text.render(ColorLevel::None), "abc");
+    /// assert_eq!(
+    ///     text.render(ColorLevel::TrueColor),
+    ///     "\x1B[38;2;255;0;0ma\x1B[38;2;128;0;128mb\x1B[38;2;0;0;255mc\x1B[0m"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn render(&self, level: ColorLevel) -> String {
+        StyledText::from(self.to_spans()).render(level)
+    }
+
+    /// Writes this gradient to `writer` at the given color level.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(
+        &self,
+        mut writer: W,
+        level: ColorLevel,
+    ) -> io::Result<()> {
+        writer.write_all(self.render(level).as_bytes())
+    }
+}
+
+impl fmt::Display for Gradient<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render(control::get_current_color_level()))
+    }
+}
@@ -0,0 +1,386 @@
+//! Rendering colored text as HTML, e.g. for CI reports and web pages.
+//!
+//! [`ColoredString::to_html`] and [`StyledText::to_html`] write each span
+//! as a `<span>`, with its colors and styles either as an inline `style`
+//! attribute or as `cnxt-` class names that [`stylesheet()`] defines. Spans
+//! with a link are wrapped in an `<a>`. Line breaks are kept as they are,
+//! so the output belongs in a `<pre>` or an element with
+//! `white-space: pre`.
+//!
+//! Colors are written as their RGB values, with the standard and 256-color
+//! palettes mapped the same way as when downgrading. The color level of the
+//! terminal does not apply. Blinking is not rendered.
+//!
+//! Output captured from a terminal can be converted with
+//! [`parse_ansi`](crate::parse_ansi):
+//!
+//! ```rust
+//! use cnxt::{StyledText, html::Styling, parse_ansi};
+//!
+//! let captured = "\x1B[1;32mok\x1B[0m 3 passed";
+//! let html = StyledText::from(parse_ansi(captured)).to_html(Styling::Inline);
+//! assert_eq!(
+//!     html,
+//!     r#"<span style="color:#008000;font-weight:bold">ok</span> 3 passed"#
+//! );
+//! ```
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+
+use crate::{Color, ColoredString, Style, StyledText, Styles, strip_ansi};
+
+/// How the colors and styles of a span are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Styling {
+    /// An inline `style` attribute, which works without a stylesheet.
+    #[default]
+    Inline,
+    /// Class names such as `cnxt-fg-red` and `cnxt-bold`, defined by
+    /// [`stylesheet()`]. Colors other than the 16 standard ones have no
+    /// class, so they are still written as an inline style.
+    Classes,
+}
+
+/// The prefix of all class names.
+const CLASS_PREFIX: &str = "cnxt-";
+
+const STANDARD_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::BrightBlack,
+    Color::BrightRed,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+    Color::BrightCyan,
+    Color::BrightWhite,
+];
+
+/// The CSS of the styles that map to declarations of their own.
+const STYLE_CSS: [(Styles, &str); 8] = [
+    (Styles::Bold, "font-weight:bold"),
+    (Styles::Dimmed, "opacity:0.5"),
+    (Styles::Italic, "font-style:italic"),
+    (Styles::Hidden, "visibility:hidden"),
+    (Styles::Framed, "border:1px solid"),
+    (Styles::Encircled, "border:1px solid;border-radius:50%"),
+    (
+        Styles::Superscript,
+        "vertical-align:super;font-size:smaller",
+    ),
+    (Styles::Subscript, "vertical-align:sub;font-size:smaller"),
+];
+
+/// The styles that share `text-decoration-line`, with their line and
+/// their `text-decoration-style`, if any.
+const DECORATIONS: [(Styles, &str, Option<&str>); 7] = [
+    (Styles::Underline, "underline", None),
+    (Styles::CurlyUnderline, "underline", Some("wavy")),
+    (Styles::DoubleUnderline, "underline", Some("double")),
+    (Styles::DottedUnderline, "underline", Some("dotted")),
+    (Styles::DashedUnderline, "underline", Some("dashed")),
+    (Styles::Strikethrough, "line-through", None),
+    (Styles::Overline, "overline", None),
+];
+
+/// Escapes the characters that are special in HTML text and attribute
+/// values: `&`, `<`, `>`, `"` and `'`.
+///
+/// Returns the input unchanged, without allocating, if there is nothing to
+/// escape.
+///
+/// ```rust
+/// use cnxt::html::escape;
+///
+/// assert_eq!(escape("<b>\"Tom\" & 'Jerry'</b>"), "&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;");
+/// assert_eq!(escape("plain"), "plain");
+/// ```
+#[must_use]
+pub fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut escaped = String::with_capacity(s.len() + 16);
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Returns the CSS that defines the class names of [`Styling::Classes`].
+///
+/// ```rust
+/// let css = cnxt::html::stylesheet();
+/// assert!(css.contains(".cnxt-fg-bright_red{color:#ff0000}"));
+/// assert!(css.contains(".cnxt-bold{font-weight:bold}"));
+/// ```
+#[must_use]
+pub fn stylesheet() -> String {
+    let mut css = String::new();
+
+    for color in STANDARD_COLORS {
+        let rgb = css_color(color);
+        for (kind, property) in [
+            ("fg", "color"),
+            ("bg", "background-color"),
+            ("ul", "text-decoration-color"),
+        ] {
+            css.push_str(&format!(
+                ".{CLASS_PREFIX}{kind}-{color}{{{property}:{rgb}}}\n"
+            ));
+        }
+    }
+
+    for (style, declarations) in STYLE_CSS {
+        css.push_str(&format!(".{CLASS_PREFIX}{style}{{{declarations}}}\n"));
+    }
+
+    // Each class sets a variable, so the lines of several classes combine
+    // in one `text-decoration-line`
+    let mut selectors = Vec::new();
+    for (style, line, decoration_style) in DECORATIONS {
+        let mut declarations = format!("--{CLASS_PREFIX}{line}:{line}");
+        if let Some(decoration_style) = decoration_style {
+            declarations.push_str(&format!(
+                ";text-decoration-style:{decoration_style}"
+            ));
+        }
+        css.push_str(&format!(".{CLASS_PREFIX}{style}{{{declarations}}}\n"));
+        selectors.push(format!(".{CLASS_PREFIX}{style}"));
+    }
+    css.push_str(&format!(
+        "{}{{text-decoration-line:var(--{CLASS_PREFIX}underline,) \
+         var(--{CLASS_PREFIX}line-through,) var(--{CLASS_PREFIX}overline,)}}\n",
+        selectors.join(",")
+    ));
+
+    css
+}
+
+impl ColoredString<'_> {
+    /// Renders this `ColoredString` as HTML.
+    ///
+    /// See the [`html`](crate::html) module for details.
+    ///
+    /// ```rust
+    /// use cnxt::{Colorize as _, html::Styling};
+    ///
+    /// let s = "a < b".red().bold();
+    /// assert_eq!(
+    ///     s.to_html(Styling::Inline),
+    ///     r#"<span style="color:#800000;font-weight:bold">a &lt; b</span>"#
+    /// );
+    /// assert_eq!(
+    ///     s.to_html(Styling::Classes),
+    ///     r#"<span class="cnxt-fg-red cnxt-bold">a &lt; b</span>"#
+    /// );
+    /// ```
+    ///
+    /// Underlines, strikethrough and overline combine in one
+    /// `text-decoration-line`:
+    ///
+    /// ```rust
+    /// use cnxt::{Colorize as _, html::Styling};
+    ///
+    /// let s = "typo".curly_underline().strikethrough().overline();
+    /// assert_eq!(
+    ///     s.to_html(Styling::Inline),
+    ///     r#"<span style="text-decoration-style:wavy;text-decoration-line:underline line-through overline">typo</span>"#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_html(&self, styling: Styling) -> String {
+        let mut out = String::new();
+        write_span(&mut out, self, styling);
+        out
+    }
+}
+
+impl StyledText<'_> {
+    /// Renders this `StyledText` as HTML, one element per span.
+    ///
+    /// See the [`html`](crate::html) module for details.
+    #[must_use]
+    pub fn to_html(&self, styling: Styling) -> String {
+        let mut out = String::new();
+        for span in self.spans() {
+            write_span(&mut out, span, styling);
+        }
+        out
+    }
+}
+
+fn write_span(out: &mut String, span: &ColoredString, styling: Styling) {
+    if let Some(link) = &span.link {
+        out.push_str(&format!("<a href=\"{}\">", escape(&link.url)));
+    }
+
+    let attributes = Attributes::of(span, styling);
+    let text = strip_ansi(&span.input);
+    if attributes.is_empty() {
+        out.push_str(&escape(&text));
+    } else {
+        out.push_str("<span");
+        if !attributes.classes.is_empty() {
+            out.push_str(&format!(
+                " class=\"{}\"",
+                attributes.classes.join(" ")
+            ));
+        }
+        if !attributes.declarations.is_empty() {
+            out.push_str(&format!(
+                " style=\"{}\"",
+                attributes.declarations.join(";")
+            ));
+        }
+        out.push('>');
+        out.push_str(&escape(&text));
+        out.push_str("</span>");
+    }
+
+    if span.link.is_some() {
+        out.push_str("</a>");
+    }
+}
+
+/// The class names and inline declarations of a span.
+#[derive(Default)]
+struct Attributes {
+    classes: Vec<String>,
+    declarations: Vec<String>,
+}
+
+impl Attributes {
+    fn of(span: &ColoredString, styling: Styling) -> Self {
+        let mut attributes = Self::default();
+
+        // Reversed colors are swapped here, with the page colors standing
+        // in for the default colors of the terminal
+        let (fg, bg) = if span.style.contains(Styles::Reversed) {
+            (
+                Some(span.bgcolor.map_or(Paint::Canvas, Paint::Color)),
+                Some(span.fgcolor.map_or(Paint::CanvasText, Paint::Color)),
+            )
+        } else {
+            (
+                span.fgcolor.map(Paint::Color),
+                span.bgcolor.map(Paint::Color),
+            )
+        };
+        let colors = [
+            (fg, "fg", "color"),
+            (bg, "bg", "background-color"),
+            (
+                span.underline_color.map(Paint::Color),
+                "ul",
+                "text-decoration-color",
+            ),
+        ];
+        for (paint, kind, property) in colors {
+            if let Some(paint) = paint {
+                attributes.push_paint(paint, kind, property, styling);
+            }
+        }
+
+        attributes.push_styles(span.style, styling);
+        attributes
+    }
+
+    fn is_empty(&self) -> bool {
+        self.classes.is_empty() && self.declarations.is_empty()
+    }
+
+    fn push_paint(
+        &mut self,
+        paint: Paint,
+        kind: &str,
+        property: &str,
+        styling: Styling,
+    ) {
+        match paint {
+            Paint::Color(color)
+                if styling == Styling::Classes
+                    && STANDARD_COLORS.contains(&color) =>
+            {
+                self.classes.push(format!("{CLASS_PREFIX}{kind}-{color}"));
+            }
+            Paint::Color(color) => {
+                self.declarations
+                    .push(format!("{property}:{}", css_color(color)));
+            }
+            Paint::Canvas => {
+                self.declarations.push(format!("{property}:Canvas"))
+            }
+            Paint::CanvasText => {
+                self.declarations.push(format!("{property}:CanvasText"));
+            }
+        }
+    }
+
+    fn push_styles(&mut self, style: Style, styling: Styling) {
+        if styling == Styling::Classes {
+            let styles = STYLE_CSS
+                .iter()
+                .map(|&(style, _)| style)
+                .chain(DECORATIONS.iter().map(|&(style, ..)| style));
+            for styles in styles.filter(|&styles| style.contains(styles)) {
+                self.classes.push(format!("{CLASS_PREFIX}{styles}"));
+            }
+            return;
+        }
+
+        for (styles, declarations) in STYLE_CSS {
+            if style.contains(styles) {
+                self.declarations.push(declarations.into());
+            }
+        }
+
+        let mut lines: Vec<&str> = Vec::new();
+        for (styles, line, decoration_style) in DECORATIONS {
+            if style.contains(styles) {
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+                if let Some(decoration_style) = decoration_style {
+                    self.declarations.push(format!(
+                        "text-decoration-style:{decoration_style}"
+                    ));
+                }
+            }
+        }
+        if !lines.is_empty() {
+            self.declarations
+                .push(format!("text-decoration-line:{}", lines.join(" ")));
+        }
+    }
+}
+
+/// A color of a span, or one of the page colors.
+#[derive(Clone, Copy)]
+enum Paint {
+    Color(Color),
+    /// The background color of the page.
+    Canvas,
+    /// The text color of the page.
+    CanvasText,
+}
+
+fn css_color(color: Color) -> String {
+    let (r, g, b) = color.to_rgb();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
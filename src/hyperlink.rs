@@ -0,0 +1,82 @@
+//! Terminal hyperlinks, written as OSC 8 escape sequences.
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+/// A URL attached to a [`ColoredString`](crate::ColoredString).
+///
+/// Created by [`Colorize::link`](crate::Colorize::link) and
+/// [`Colorize::link_with_id`](crate::Colorize::link_with_id). The link is
+/// only written when colors are enabled, see
+/// [`Colorize::link`](crate::Colorize::link) for the details.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hyperlink<'a> {
+    /// The target of the link.
+    pub url: Cow<'a, str>,
+    /// Links with the same id and URL are highlighted together, e.g. when a
+    /// link is split over several lines.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub id: Option<Cow<'a, str>>,
+}
+
+impl<'a> Hyperlink<'a> {
+    /// Creates a link to `url` without an id.
+    pub fn new(url: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            url: url.into(),
+            id: None,
+        }
+    }
+
+    /// Sets the id of the link.
+    #[must_use]
+    pub fn with_id(mut self, id: impl Into<Cow<'a, str>>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Writes the sequence that opens the link.
+    pub(crate) fn fmt_open(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1B]8;")?;
+        if let Some(id) = &self.id {
+            f.write_str("id=")?;
+            // `:` and `;` separate the parameters
+            write_escaped(f, id, |c| matches!(c, ':' | ';'))?;
+        }
+        f.write_str(";")?;
+        write_escaped(f, &self.url, |_| false)?;
+        f.write_str("\x1B\\")
+    }
+
+    /// Writes the sequence that closes any open link.
+    pub(crate) fn fmt_close(f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1B]8;;\x1B\\")
+    }
+}
+
+/// Writes `s`, percent-encoding everything that is not printable ASCII, as
+/// well as the characters `reserved` returns true for, so the text cannot
+/// end the escape sequence early.
+fn write_escaped(
+    f: &mut impl fmt::Write,
+    s: &str,
+    reserved: impl Fn(char) -> bool,
+) -> fmt::Result {
+    let mut rest = s;
+    while let Some(idx) =
+        rest.find(|c: char| !(' '..='~').contains(&c) || reserved(c))
+    {
+        f.write_str(&rest[..idx])?;
+
+        let c = rest[idx..].chars().next().unwrap_or_default();
+        let mut buf = [0; 4];
+        for byte in c.encode_utf8(&mut buf).bytes() {
+            write!(f, "%{byte:02X}")?;
+        }
+
+        rest = &rest[idx + c.len_utf8()..];
+    }
+
+    f.write_str(rest)
+}
@@ -38,6 +38,7 @@
 //!     use cnxt::control::{set_should_colorize, ShouldColorize};
 //!     
 //!     // Environment-based detection level (default)
+//!     # #[cfg(feature = "std")]
 //!     set_should_colorize(ShouldColorize::from_env());
 //!
 //!     // Explicit configuration
@@ -52,6 +53,14 @@
 //!     let truecolor = Color::TrueColor { r: 166, g: 227, b: 161 };
 //!     let ansi16 = truecolor.fallback_to_ansi16();
 //!     let ansi256 = truecolor.fallback_to_ansi256();
+//!
+//!     // Match colors perceptually instead of by RGB distance when downgrading
+//!     use cnxt::{control::set_color_distance, ColorDistance};
+//!
+//!     set_color_distance(ColorDistance::OkLab);
+//!
+//!     // Write `.link(url)`s as clickable OSC 8 hyperlinks, detected from the environment by default
+//!     cnxt::control::set_hyperlinks(true);
 //!     ```
 //!
 //! ### Features
@@ -65,31 +74,123 @@
 //!    Provide helper functions to colorize strings based on conditions.
 //!
 //!     ```rust
+//!     # #[cfg(feature = "conditional-coloring")] {
 //!     use cnxt::Colorize as _;
 //!     
 //!     println!("{}", "red".red_if(true)); // print red color
 //!     println!("{}", "red".red_if(false)); // print no color
 //!
 //!     println!("{}", "green".green().red_if(false)); // print green color
+//!     # }
+//!     ```
+//!
+//! 3. **toml** / **json** :
+//!    Load a [`theme::Theme`] of semantic roles from TOML or JSON.
+//!
+//!     ```rust
+//!     # #[cfg(feature = "toml")] {
+//!     use cnxt::{theme::{self, Theme}, Colorize as _};
+//!
+//!     let theme = Theme::from_toml(r#"error = "bold bright_red""#).unwrap();
+//!     theme::set_theme(theme);
+//!
+//!     println!("{}", "disk full".role("error"));
+//!     # }
+//!     ```
+//!
+//!    Roles of the default theme can also be overridden with the `CNXT_THEME` environment variable, e.g.
+//!    `CNXT_THEME="error=bold magenta;path=blue"`.
+//!
+//! 4. **terminfo** :
+//!    Read the color capabilities of `TERM` from the terminfo database when detecting the color level,
+//!    so terminals like `xterm-direct`, `rxvt-unicode` and `linux` are classified correctly.
+//!    See [`terminfo`](https://docs.rs/cnxt/latest/cnxt/terminfo/index.html).
+//!
+//! 5. **serde** :
+//!    Serialize and deserialize [`Color`], [`Style`], [`Styles`], [`CustomColor`] and [`ColoredString`]. Colors
+//!    are written as strings like `"red"`, `"#ff8800"` or `"ansi256(208)"`, and styles as lists of names.
+//!
+//! 6. **std** (Enabled by default):
+//!    Without it, the crate is `no_std` and only needs `alloc`. The colors, styles and rendering are
+//!    available, but nothing that reads the environment or the terminal: colors are not detected,
+//!    and the themes, `LS_COLORS` and per-thread overrides are left out. Render with an explicit
+//!    level, e.g. `"text".red().render(ColorLevel::Ansi16)`, or set the level used by `Display` with
+//!    `control::set_should_colorize`.
+//!
+//! 7. **macros** :
+//!    `cformat!`, `cprintln!` and friends, which take inline markup in the format string.
+//!    Tags are checked at compile time.
+//!
+//!     ```rust
+//!     # #[cfg(all(feature = "macros", feature = "std"))] {
+//!     use cnxt::cprintln;
+//!
+//!     let path = "/etc/hosts";
+//!     cprintln!("<bold red>error</>: cannot read <underline>{path}</>");
+//!     # }
 //!     ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod color;
+mod colorspace;
 pub mod control;
+mod gradient;
+pub mod html;
+mod hyperlink;
+#[cfg(feature = "std")]
+pub mod lscolors;
+#[cfg(feature = "macros")]
+mod markup;
+mod math;
+mod sgr;
 mod style;
+mod styled_text;
+#[cfg(feature = "terminfo")]
+pub mod terminfo;
+#[cfg(feature = "std")]
+pub mod theme;
 
 pub use self::customcolors::CustomColor;
 
 /// Custom colors support.
 pub mod customcolors;
 
-use std::{
+use alloc::{
     borrow::Cow,
+    string::{String, ToString as _},
+    vec::Vec,
+};
+use core::{
     fmt,
     ops::{Deref, DerefMut},
 };
+#[cfg(feature = "std")]
+use std::io;
+
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthStr as _;
 
 pub use color::*;
-pub use style::{Style, Styles};
+pub use gradient::{Gradient, Interpolation};
+pub use hyperlink::Hyperlink;
+pub use sgr::parse_ansi;
+pub use style::{ParseStyleError, Style, Styles};
+pub use styled_text::{StyledDisplay, StyledText};
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::format;
+    #[cfg(feature = "std")]
+    pub use std::{eprint, eprintln, print, println};
+
+    pub use cnxt_macros::format_markup;
+
+    pub use crate::markup::{Markup, MarkupState};
+}
 
 /// A string that may have color and/or style applied to it.
 ///
@@ -157,7 +258,36 @@ pub use style::{Style, Styles};
 /// ```
 ///
 /// Notice how this process preserves the coloring and style.
+///
+/// ## Serialization
+///
+/// With the `serde` feature, a `ColoredString` is serialized as a struct of
+/// its fields. Colors are written as strings like `"red"`, `"#ff8800"` or
+/// `"ansi256(208)"`, and the style as a list of style names. Missing fields
+/// take their default value when deserializing.
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use cnxt::{ColoredString, Colorize as _};
+///
+/// let warning = "careful".yellow().bold().on_truecolor(40, 40, 40);
+/// let json = serde_json::to_string(&warning).unwrap();
+/// assert_eq!(
+///     json,
+///     r##"{"input":"careful","fgcolor":"yellow","bgcolor":"#282828","style":["bold"],"underline_color":null,"link":null}"##
+/// );
+///
+/// let restored: ColoredString = serde_json::from_str(&json).unwrap();
+/// assert_eq!(restored, warning);
+///
+/// let minimal: ColoredString =
+///     serde_json::from_str(r#"{"input":"ok","fgcolor":"green"}"#).unwrap();
+/// assert_eq!(minimal, "ok".green());
+/// # }
+/// ```
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[non_exhaustive]
 pub struct ColoredString<'a> {
     /// The plain text that will have color and style applied to it.
@@ -170,6 +300,11 @@ pub struct ColoredString<'a> {
     /// Any special styling to be applied to the text (see Styles for a list of
     /// available options).
     pub style: Style,
+    /// The color of the underline, if any. None means that the underline
+    /// takes the color of the text.
+    pub underline_color: Option<Color>,
+    /// The URL the text links to, if any.
+    pub link: Option<Hyperlink<'a>>,
 }
 
 // Define macros to generate color methods
@@ -323,9 +458,18 @@ pub trait Colorize<'a> {
     {
         self.color(parse_hex(hex.as_ref()).unwrap())
     }
-    /// The following `#` prefix is optional.
+    /// The following `#` prefix is optional, and the alpha digits of
+    /// `#rgba` and `#rrggbbaa` are ignored.
     ///
     /// This function will return `None` if the hex string is invalid
+    ///
+    /// ```rust
+    /// use cnxt::Colorize as _;
+    ///
+    /// assert!("x".try_hexcolor("#ff880080").is_some());
+    /// assert!("x".try_hexcolor("+ff").is_none());
+    /// assert!("x".try_hexcolor("aé€").is_none());
+    /// ```
     fn try_hexcolor<S>(self, hex: S) -> Option<ColoredString<'a>>
     where
         Self: Sized,
@@ -441,6 +585,124 @@ pub trait Colorize<'a> {
     }
     fn on_color<S: Into<Color>>(self, color: S) -> ColoredString<'a>;
 
+    /// Sets the color of the underline, independently of the text color.
+    ///
+    /// This has no visible effect unless the text is underlined.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, Colorize as _, control::ColorLevel};
+    ///
+    /// let marker = "unused".curly_underline().underline_color(Color::Red);
+    /// assert_eq!(
+    ///     marker.render(ColorLevel::TrueColor),
+    ///     "\x1B[4:3;58;5;1munused\x1B[0m"
+    /// );
+    /// ```
+    fn underline_color<S: Into<Color>>(self, color: S) -> ColoredString<'a>;
+
+    /// Colors each grapheme with a color interpolated between `stops`.
+    ///
+    /// See [`Gradient`] for details.
+    fn gradient<C: Into<CustomColor>>(
+        self,
+        stops: impl IntoIterator<Item = C>,
+    ) -> Gradient<'a>
+    where
+        Self: Sized + Into<ColoredString<'a>>,
+    {
+        Gradient::new(self).gradient(stops)
+    }
+    /// Colors the background of each grapheme with a color interpolated
+    /// between `stops`.
+    ///
+    /// See [`Gradient`] for details.
+    fn on_gradient<C: Into<CustomColor>>(
+        self,
+        stops: impl IntoIterator<Item = C>,
+    ) -> Gradient<'a>
+    where
+        Self: Sized + Into<ColoredString<'a>>,
+    {
+        Gradient::new(self).on_gradient(stops)
+    }
+
+    /// Turns the text into a hyperlink to `url`.
+    ///
+    /// The link is written as an OSC 8 escape sequence when colors are
+    /// enabled. When displayed, it is only written if the terminal supports
+    /// hyperlinks, see [`control::set_hyperlinks()`]. When rendered at an
+    /// explicit level, it is written unless turned off with
+    /// [`ColoredDisplay::hyperlinks`]. Otherwise only the text is written.
+    ///
+    /// ```rust
+    /// use cnxt::{Colorize as _, control::ColorLevel};
+    ///
+    /// let docs = "docs".link("https://docs.rs/cnxt");
+    /// assert_eq!(
+    ///     docs.render(ColorLevel::TrueColor),
+    ///     "\x1B]8;;https://docs.rs/cnxt\x1B\\docs\x1B]8;;\x1B\\"
+    /// );
+    /// assert_eq!(docs.render(ColorLevel::None), "docs");
+    /// assert_eq!(
+    ///     docs.display_at(ColorLevel::TrueColor).hyperlinks(false).to_string(),
+    ///     "docs"
+    /// );
+    ///
+    /// let issue = "#42".blue().link("https://github.com/cnlancehu/cnxt/issues/42");
+    /// assert_eq!(
+    ///     issue.render(ColorLevel::Ansi16),
+    ///     "\x1B]8;;https://github.com/cnlancehu/cnxt/issues/42\x1B\\\x1B[34m#42\x1B[0m\x1B]8;;\x1B\\"
+    /// );
+    /// ```
+    fn link(self, url: impl Into<Cow<'a, str>>) -> ColoredString<'a>
+    where
+        Self: Sized + Into<ColoredString<'a>>,
+    {
+        let mut s = self.into();
+        s.link = Some(Hyperlink::new(url));
+        s
+    }
+    /// Turns the text into a hyperlink to `url` with the given `id`.
+    ///
+    /// Terminals highlight all links with the same id and URL together,
+    /// which is useful for links that are split over several lines.
+    ///
+    /// See [`Self::link`] for details.
+    fn link_with_id(
+        self,
+        url: impl Into<Cow<'a, str>>,
+        id: impl Into<Cow<'a, str>>,
+    ) -> ColoredString<'a>
+    where
+        Self: Sized + Into<ColoredString<'a>>,
+    {
+        let mut s = self.into();
+        s.link = Some(Hyperlink::new(url).with_id(id));
+        s
+    }
+
+    /// Styles the text as the semantic `role` of the active theme, e.g.
+    /// `"error"` or `"path"`.
+    ///
+    /// The text is returned unchanged if the theme does not have `role`.
+    /// See the [`theme`] module for details.
+    ///
+    /// ```rust
+    /// use cnxt::{Colorize as _, control::ColorLevel, theme::{Theme, with_theme}};
+    ///
+    /// with_theme(Theme::new(), || {
+    ///     let s = "disk full".role("error");
+    ///     assert_eq!(s.render(ColorLevel::Ansi16), "\x1B[1;31mdisk full\x1B[0m");
+    /// });
+    /// ```
+    #[cfg(feature = "std")]
+    fn role(self, role: &str) -> ColoredString<'a>
+    where
+        Self: Sized + Into<ColoredString<'a>>,
+    {
+        theme::get_theme().apply(role, self)
+    }
+
     // Styles
     fn clear(self) -> ColoredString<'a>;
     fn normal(self) -> ColoredString<'a>;
@@ -452,6 +714,16 @@ pub trait Colorize<'a> {
     fn reversed(self) -> ColoredString<'a>;
     fn hidden(self) -> ColoredString<'a>;
     fn strikethrough(self) -> ColoredString<'a>;
+    fn curly_underline(self) -> ColoredString<'a>;
+    fn double_underline(self) -> ColoredString<'a>;
+    fn dotted_underline(self) -> ColoredString<'a>;
+    fn dashed_underline(self) -> ColoredString<'a>;
+    fn overline(self) -> ColoredString<'a>;
+    fn rapid_blink(self) -> ColoredString<'a>;
+    fn framed(self) -> ColoredString<'a>;
+    fn encircled(self) -> ColoredString<'a>;
+    fn superscript(self) -> ColoredString<'a>;
+    fn subscript(self) -> ColoredString<'a>;
 }
 
 impl ColoredString<'_> {
@@ -466,6 +738,12 @@ impl ColoredString<'_> {
         self.bgcolor = None;
     }
 
+    /// Gets rid of this `ColoredString`'s underline color, so the underline
+    /// takes the color of the text again.
+    pub fn clear_underline_color(&mut self) {
+        self.underline_color = None;
+    }
+
     /// Clears any special styling and sets it back to the default (plain,
     /// maybe colored, text).
     pub fn clear_style(&mut self) {
@@ -485,15 +763,78 @@ impl ColoredString<'_> {
     pub fn is_plain(&self) -> bool {
         self.bgcolor.is_none()
             && self.fgcolor.is_none()
+            && self.underline_color.is_none()
             && self.style == style::CLEAR
     }
 
-    fn has_colors() -> bool {
-        control::get_current_color_level() != control::ColorLevel::None
+    /// Returns a value that displays this `ColoredString` with the color
+    /// level configured for `stream`, rather than the one for stdout.
+    ///
+    /// ```rust
+    /// # use cnxt::*;
+    /// use cnxt::control::Stream;
+    ///
+    /// eprintln!("{}", "warning".yellow().display_for(Stream::Stderr));
+    /// ```
+    #[must_use]
+    pub fn display_for(&self, stream: control::Stream) -> ColoredDisplay<'_> {
+        self.display_at(control::get_color_level_for(stream))
+            .hyperlinks(control::get_hyperlinks())
+    }
+
+    /// Returns a value that displays this `ColoredString` at the given color
+    /// level, ignoring the global and thread-local settings.
+    ///
+    /// The link, if any, is written unless `level` is [`ColorLevel::None`]
+    /// or it is turned off with [`ColoredDisplay::hyperlinks`].
+    ///
+    /// [`ColorLevel::None`]: control::ColorLevel::None
+    #[must_use]
+    pub fn display_at(&self, level: control::ColorLevel) -> ColoredDisplay<'_> {
+        ColoredDisplay {
+            inner: self,
+            level,
+            hyperlinks: true,
+        }
+    }
+
+    /// Renders this `ColoredString` at the given color level.
+    ///
+    /// Colors beyond `level` are downgraded, and [`ColorLevel::None`] yields
+    /// the plain text. The global settings are neither read nor modified,
+    /// so the link, if any, is written at every other level. Use
+    /// [`ColoredString::display_at`] to leave it out.
+    ///
+    /// ```rust
+    /// # use cnxt::*;
+    /// use cnxt::control::ColorLevel;
+    ///
+    /// let s = "Ok".truecolor(0, 255, 0).bold();
+    /// assert_eq!(s.render(ColorLevel::TrueColor), "\x1B[1;38;2;0;255;0mOk\x1B[0m");
+    /// assert_eq!(s.render(ColorLevel::Ansi16), "\x1B[1;92mOk\x1B[0m");
+    /// assert_eq!(s.render(ColorLevel::None), "Ok");
+    /// ```
+    ///
+    /// [`ColorLevel::None`]: control::ColorLevel::None
+    #[must_use]
+    pub fn render(&self, level: control::ColorLevel) -> String {
+        self.display_at(level).to_string()
+    }
+
+    /// Writes this `ColoredString` to `writer` at the given color level.
+    ///
+    /// See [`ColoredString::render`] for details.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(
+        &self,
+        mut writer: W,
+        level: control::ColorLevel,
+    ) -> io::Result<()> {
+        write!(writer, "{}", self.display_at(level))
     }
 
-    fn compute_style(&self) -> String {
-        if !Self::has_colors() || self.is_plain() {
+    pub(crate) fn compute_style(&self, level: control::ColorLevel) -> String {
+        if level == control::ColorLevel::None || self.is_plain() {
             return String::new();
         }
 
@@ -510,7 +851,7 @@ impl ColoredString<'_> {
                 res.push(';');
             }
 
-            res.push_str(&bgcolor.to_bg_str());
+            res.push_str(&bgcolor.to_bg_str_at(level));
             has_wrote = true;
         }
 
@@ -519,15 +860,27 @@ impl ColoredString<'_> {
                 res.push(';');
             }
 
-            res.push_str(&fgcolor.to_fg_str());
+            res.push_str(&fgcolor.to_fg_str_at(level));
+            has_wrote = true;
+        }
+
+        if let Some(ref underline_color) = self.underline_color {
+            if has_wrote {
+                res.push(';');
+            }
+
+            res.push_str(&underline_color.to_underline_str_at(level));
         }
 
         res.push('m');
         res
     }
 
-    fn escape_inner_reset_sequences(&self) -> Cow<str> {
-        if !Self::has_colors() || self.is_plain() {
+    pub(crate) fn escape_inner_reset_sequences(
+        &self,
+        level: control::ColorLevel,
+    ) -> Cow<'_, str> {
+        if level == control::ColorLevel::None || self.is_plain() {
             return Cow::Borrowed(self.input.as_ref());
         }
 
@@ -536,7 +889,7 @@ impl ColoredString<'_> {
             return Cow::Borrowed(self.input.as_ref());
         }
 
-        let style = self.compute_style();
+        let style = self.compute_style(level);
         let matches: Vec<_> = self.input.match_indices(reset).collect();
 
         let additional_space = matches.len() * style.len();
@@ -558,6 +911,237 @@ impl ColoredString<'_> {
 
         Cow::Owned(result)
     }
+
+    fn fmt_at(
+        &self,
+        f: &mut fmt::Formatter,
+        level: control::ColorLevel,
+        hyperlinks: bool,
+    ) -> fmt::Result {
+        let link = self
+            .link
+            .as_ref()
+            .filter(|_| level != control::ColorLevel::None && hyperlinks);
+        if let Some(link) = link {
+            link.fmt_open(f)?;
+        }
+
+        if level == control::ColorLevel::None || self.is_plain() {
+            write!(f, "{}", self.input)?;
+        } else {
+            let escaped_input = self.escape_inner_reset_sequences(level);
+
+            f.write_str(&self.compute_style(level))?;
+            write!(f, "{}", escaped_input)?;
+            f.write_str("\x1B[0m")?;
+        }
+
+        if link.is_some() {
+            Hyperlink::fmt_close(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a [`ColoredString`] at a fixed color level.
+///
+/// Created by [`ColoredString::display_for`] and
+/// [`ColoredString::display_at`].
+#[derive(Clone, Copy, Debug)]
+pub struct ColoredDisplay<'r> {
+    inner: &'r ColoredString<'r>,
+    level: control::ColorLevel,
+    hyperlinks: bool,
+}
+
+impl ColoredDisplay<'_> {
+    /// Sets whether the link of the `ColoredString` is written as an OSC 8
+    /// hyperlink.
+    #[must_use]
+    pub fn hyperlinks(mut self, enabled: bool) -> Self {
+        self.hyperlinks = enabled;
+        self
+    }
+}
+
+impl fmt::Display for ColoredDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt_at(f, self.level, self.hyperlinks)
+    }
+}
+
+/// Removes all ANSI escape sequences from `s`.
+///
+/// This covers SGR and other CSI sequences, OSC sequences such as
+/// hyperlinks and window titles, the other string sequences (DCS, SOS, PM,
+/// APC) and two-character escapes. Returns the input unchanged, without
+/// allocating, if it has no escape sequences.
+///
+/// ```rust
+/// use cnxt::{Colorize as _, control::ColorLevel, strip_ansi};
+///
+/// let colored = "error".red().bold().render(ColorLevel::TrueColor);
+/// assert_eq!(strip_ansi(&colored), "error");
+/// assert_eq!(strip_ansi("\x1B]8;;https://example.com\x1B\\link\x1B]8;;\x1B\\"), "link");
+/// ```
+#[must_use]
+pub fn strip_ansi(s: &str) -> Cow<'_, str> {
+    let mut segments = AnsiSegments::new(s).peekable();
+    match segments.peek() {
+        None => return Cow::Borrowed(""),
+        Some(&AnsiSegment::Text(text)) if text.len() == s.len() => {
+            return Cow::Borrowed(s);
+        }
+        _ => {}
+    }
+
+    let mut result = String::with_capacity(s.len());
+    for segment in segments {
+        if let AnsiSegment::Text(text) = segment {
+            result.push_str(text);
+        }
+    }
+
+    Cow::Owned(result)
+}
+
+/// Returns the number of terminal columns `s` takes up when printed.
+///
+/// Escape sequences take no space, wide characters such as CJK ideographs
+/// and emoji take two columns, and each grapheme cluster is measured as a
+/// whole, so combining marks and emoji sequences are not counted twice.
+///
+/// ```rust
+/// use cnxt::{Colorize as _, control::ColorLevel, visible_width};
+///
+/// let colored = "日本".red().render(ColorLevel::TrueColor);
+/// assert_eq!(visible_width(&colored), 4);
+/// assert_eq!(visible_width("e\u{301}"), 1);
+/// assert_eq!(visible_width("👨‍👩‍👧"), 2);
+/// ```
+#[must_use]
+pub fn visible_width(s: &str) -> usize {
+    AnsiSegments::new(s)
+        .filter_map(|segment| match segment {
+            AnsiSegment::Text(text) => Some(text),
+            AnsiSegment::Escape(_) => None,
+        })
+        .flat_map(|text| text.graphemes(true))
+        .map(|grapheme| grapheme.width().min(2))
+        .sum()
+}
+
+/// A piece of a string, as split by [`AnsiSegments`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AnsiSegment<'a> {
+    /// Printable text without escape sequences.
+    Text(&'a str),
+    /// A single, complete escape sequence including its introducer.
+    Escape(&'a str),
+}
+
+/// Splits a string into text and escape sequences.
+pub(crate) struct AnsiSegments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> AnsiSegments<'a> {
+    pub(crate) fn new(s: &'a str) -> Self {
+        Self { rest: s }
+    }
+}
+
+impl<'a> Iterator for AnsiSegments<'a> {
+    type Item = AnsiSegment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let len = match escape_sequence_len(self.rest) {
+            Some(len) => len,
+            None => self
+                .rest
+                .char_indices()
+                .skip(1)
+                .find(|&(idx, _)| {
+                    escape_sequence_len(&self.rest[idx..]).is_some()
+                })
+                .map_or(self.rest.len(), |(idx, _)| idx),
+        };
+
+        let (segment, rest) = self.rest.split_at(len);
+        self.rest = rest;
+
+        Some(if escape_sequence_len(segment).is_some() {
+            AnsiSegment::Escape(segment)
+        } else {
+            AnsiSegment::Text(segment)
+        })
+    }
+}
+
+/// Returns the byte length of the escape sequence `s` starts with, if any.
+///
+/// Unterminated sequences extend to the end of the string.
+fn escape_sequence_len(s: &str) -> Option<usize> {
+    const CSI: char = '\u{9b}';
+    const OSC: char = '\u{9d}';
+
+    let mut chars = s.char_indices();
+    let (_, first) = chars.next()?;
+    let body_start = match first {
+        '\x1B' => match chars.next() {
+            None => return Some(1),
+            Some((_, '[')) => return Some(2 + csi_len(&s[2..])),
+            Some((_, ']' | 'P' | 'X' | '^' | '_')) => 2,
+            // Two-character escapes, optionally with intermediate bytes
+            Some((idx, c)) => {
+                let tail = &s[idx..];
+                let len = tail
+                    .find(|c: char| !('\x20'..='\x2F').contains(&c))
+                    .map_or(tail.len(), |end| {
+                        end + tail[end..]
+                            .chars()
+                            .next()
+                            .map_or(0, char::len_utf8)
+                    });
+                return Some(if c.is_ascii() { idx + len } else { idx });
+            }
+        },
+        CSI => return Some(CSI.len_utf8() + csi_len(&s[CSI.len_utf8()..])),
+        OSC => OSC.len_utf8(),
+        _ => return None,
+    };
+
+    // String sequences end with BEL or ST (`ESC \` or U+009C)
+    let body = &s[body_start..];
+    let len = body
+        .char_indices()
+        .find_map(|(idx, c)| match c {
+            '\x07' | '\u{9c}' => Some(idx + c.len_utf8()),
+            '\x1B' if body[idx + 1..].starts_with('\\') => Some(idx + 2),
+            _ => None,
+        })
+        .unwrap_or(body.len());
+
+    Some(body_start + len)
+}
+
+/// Returns the length of a CSI sequence after its introducer: parameter
+/// and intermediate bytes followed by a final byte.
+fn csi_len(s: &str) -> usize {
+    s.bytes()
+        .position(|b| !(0x20..=0x3F).contains(&b))
+        .map_or(s.len(), |end| {
+            if (0x40..=0x7E).contains(&s.as_bytes()[end]) {
+                end + 1
+            } else {
+                // Malformed, drop what was read so far
+                end
+            }
+        })
 }
 
 impl Deref for ColoredString<'_> {
@@ -593,6 +1177,19 @@ macro_rules! impl_coloredstring_style_methods {
     }
 }
 
+/// Like `impl_coloredstring_style_methods!`, for the underline styles,
+/// which replace each other.
+macro_rules! impl_coloredstring_underline_methods {
+    ($(($method:ident, $style:expr)),*) => {
+        $(
+            fn $method(mut self) -> ColoredString<'a> {
+                self.style.set_underline($style);
+                self
+            }
+        )*
+    }
+}
+
 impl<'a> Colorize<'a> for ColoredString<'a> {
     #[cfg(feature = "conditional-coloring")]
     fn do_nothing(self) -> ColoredString<'a> {
@@ -607,10 +1204,18 @@ impl<'a> Colorize<'a> for ColoredString<'a> {
         self.bgcolor = Some(color.into());
         self
     }
+    fn underline_color<S: Into<Color>>(
+        mut self,
+        color: S,
+    ) -> ColoredString<'a> {
+        self.underline_color = Some(color.into());
+        self
+    }
 
     fn clear(self) -> ColoredString<'a> {
         Self {
             input: self.input,
+            link: self.link,
             ..Self::default()
         }
     }
@@ -622,11 +1227,24 @@ impl<'a> Colorize<'a> for ColoredString<'a> {
         (bold, Styles::Bold),
         (dimmed, Styles::Dimmed),
         (italic, Styles::Italic),
-        (underline, Styles::Underline),
         (blink, Styles::Blink),
         (reversed, Styles::Reversed),
         (hidden, Styles::Hidden),
-        (strikethrough, Styles::Strikethrough)
+        (strikethrough, Styles::Strikethrough),
+        (overline, Styles::Overline),
+        (rapid_blink, Styles::RapidBlink),
+        (framed, Styles::Framed),
+        (encircled, Styles::Encircled),
+        (superscript, Styles::Superscript),
+        (subscript, Styles::Subscript)
+    }
+
+    impl_coloredstring_underline_methods! {
+        (underline, Styles::Underline),
+        (curly_underline, Styles::CurlyUnderline),
+        (double_underline, Styles::DoubleUnderline),
+        (dotted_underline, Styles::DottedUnderline),
+        (dashed_underline, Styles::DashedUnderline)
     }
 }
 
@@ -662,6 +1280,14 @@ impl<'a> Colorize<'a> for &'a str {
         }
     }
 
+    fn underline_color<S: Into<Color>>(self, color: S) -> ColoredString<'a> {
+        ColoredString {
+            underline_color: Some(color.into()),
+            input: Cow::Borrowed(self),
+            ..ColoredString::default()
+        }
+    }
+
     fn clear(self) -> ColoredString<'a> {
         ColoredString {
             input: Cow::Borrowed(self),
@@ -681,47 +1307,26 @@ impl<'a> Colorize<'a> for &'a str {
         (blink),
         (reversed),
         (hidden),
-        (strikethrough)
+        (strikethrough),
+        (curly_underline),
+        (double_underline),
+        (dotted_underline),
+        (dashed_underline),
+        (overline),
+        (rapid_blink),
+        (framed),
+        (encircled),
+        (superscript),
+        (subscript)
     }
 }
 
 impl fmt::Display for ColoredString<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if !Self::has_colors() || self.is_plain() {
-            return write!(f, "{}", self.input);
-        }
-
-        let escaped_input = self.escape_inner_reset_sequences();
-
-        f.write_str(&self.compute_style())?;
-        write!(f, "{}", escaped_input)?;
-        f.write_str("\x1B[0m")?;
-        Ok(())
-    }
-}
-
-fn parse_hex(s: &str) -> Option<Color> {
-    // Remove leading # if present
-    let s = s.strip_prefix('#').unwrap_or(s);
-
-    match s.len() {
-        6 => {
-            let r = u8::from_str_radix(&s[0..2], 16).ok()?;
-            let g = u8::from_str_radix(&s[2..4], 16).ok()?;
-            let b = u8::from_str_radix(&s[4..6], 16).ok()?;
-            Some(Color::TrueColor { r, g, b })
-        }
-        3 => {
-            // Convert hex shorthand (e.g. "f09") to full form by duplicating each digit
-            let r = u8::from_str_radix(&s[0..1], 16).ok()?;
-            let g = u8::from_str_radix(&s[1..2], 16).ok()?;
-            let b = u8::from_str_radix(&s[2..3], 16).ok()?;
-            Some(Color::TrueColor {
-                r: r * 17, // Same as (r << 4) | r but more readable
-                g: g * 17,
-                b: b * 17,
-            })
-        }
-        _ => None,
+        self.fmt_at(
+            f,
+            control::get_current_color_level(),
+            control::get_hyperlinks(),
+        )
     }
 }
@@ -0,0 +1,381 @@
+//! Colorizing paths the way `ls` does, with the colors from `LS_COLORS`.
+//!
+//! `LS_COLORS` is a `:`-separated list of `key=value` entries, where the
+//! value is a list of SGR parameters such as `01;34`. Keys are either a
+//! two-letter file type indicator like `di` for directories, or a `*`
+//! followed by a file name suffix, usually an extension like `*.tar`.
+//!
+//! # Example
+//! ```rust
+//! use cnxt::{Color, lscolors::{Indicator, LsColors}};
+//!
+//! let ls_colors = LsColors::parse("di=01;34:ln=target:*.rs=38;5;208");
+//!
+//! let dir = ls_colors.style_for_indicator(Indicator::Directory).unwrap();
+//! assert_eq!(dir.fg, Some(Color::Blue));
+//!
+//! let rs = ls_colors.style_for_file_name("main.rs").unwrap();
+//! assert_eq!(rs.fg, Some(Color::Ansi256 { idx: 208 }));
+//!
+//! // Colorize a path based on its file type and extension
+//! println!("{}", ls_colors.colorize_path("Cargo.toml".as_ref()));
+//! ```
+
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, Metadata},
+    path::Path,
+};
+
+use crate::{ColoredString, sgr::SgrState, theme::StyleSpec};
+
+/// The file types and other kinds of text `LS_COLORS` has colors for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Indicator {
+    /// `no`: text that is not a file name, and the fallback for files.
+    Normal,
+    /// `fi`: a regular file.
+    RegularFile,
+    /// `di`: a directory.
+    Directory,
+    /// `ln`: a symbolic link.
+    Symlink,
+    /// `pi`: a named pipe.
+    Fifo,
+    /// `so`: a socket.
+    Socket,
+    /// `do`: a door.
+    Door,
+    /// `bd`: a block device.
+    BlockDevice,
+    /// `cd`: a character device.
+    CharDevice,
+    /// `or`: a symbolic link to a file that does not exist.
+    OrphanedSymlink,
+    /// `mi`: a file that does not exist.
+    MissingFile,
+    /// `su`: a file that is setuid.
+    Setuid,
+    /// `sg`: a file that is setgid.
+    Setgid,
+    /// `ca`: a file with capabilities.
+    Capability,
+    /// `tw`: a directory that is sticky and writable by others.
+    StickyOtherWritable,
+    /// `ow`: a directory that is writable by others.
+    OtherWritable,
+    /// `st`: a directory that is sticky.
+    Sticky,
+    /// `ex`: an executable file.
+    Executable,
+    /// `mh`: a file with more than one hard link.
+    MultipleHardLinks,
+}
+
+impl Indicator {
+    /// Returns the indicator for a two-letter `LS_COLORS` key.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let indicator = match key {
+            "no" => Self::Normal,
+            "fi" => Self::RegularFile,
+            "di" => Self::Directory,
+            "ln" => Self::Symlink,
+            "pi" => Self::Fifo,
+            "so" => Self::Socket,
+            "do" => Self::Door,
+            "bd" => Self::BlockDevice,
+            "cd" => Self::CharDevice,
+            "or" => Self::OrphanedSymlink,
+            "mi" => Self::MissingFile,
+            "su" => Self::Setuid,
+            "sg" => Self::Setgid,
+            "ca" => Self::Capability,
+            "tw" => Self::StickyOtherWritable,
+            "ow" => Self::OtherWritable,
+            "st" => Self::Sticky,
+            "ex" => Self::Executable,
+            "mh" => Self::MultipleHardLinks,
+            _ => return None,
+        };
+
+        Some(indicator)
+    }
+}
+
+/// The colors from an `LS_COLORS` specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LsColors {
+    indicators: HashMap<Indicator, StyleSpec>,
+    /// File name suffixes, lowercased, in the order they were defined.
+    suffixes: Vec<(String, StyleSpec)>,
+    /// Whether symbolic links take the style of their target (`ln=target`).
+    link_as_target: bool,
+}
+
+impl Default for LsColors {
+    /// The colors GNU `ls` uses when `LS_COLORS` is not set.
+    fn default() -> Self {
+        Self::parse(
+            "di=01;34:ln=01;36:pi=33:so=01;35:do=01;35:bd=01;33:cd=01;33:\
+             ex=01;32:su=37;41:sg=30;43:st=37;44:ow=34;42:tw=30;42",
+        )
+    }
+}
+
+impl LsColors {
+    /// Parses the `LS_COLORS` environment variable, or returns the default
+    /// colors of GNU `ls` if it's not set.
+    #[must_use]
+    pub fn from_env() -> Self {
+        env::var("LS_COLORS")
+            .map(|var| Self::parse(&var))
+            .unwrap_or_default()
+    }
+
+    /// Parses an `LS_COLORS` specification.
+    ///
+    /// Unknown keys and malformed entries are skipped, like `ls` does. File
+    /// type indicators with an empty value, `0` or `00`, such as the `mh=00`
+    /// of `dircolors`, are not colored, so the next indicator or the suffix
+    /// applies instead.
+    #[must_use]
+    pub fn parse(s: &str) -> Self {
+        let mut ls_colors = Self {
+            indicators: HashMap::new(),
+            suffixes: Vec::new(),
+            link_as_target: false,
+        };
+
+        for (key, value) in s.split(':').filter_map(|e| e.split_once('=')) {
+            if let Some(suffix) = key.strip_prefix('*') {
+                ls_colors
+                    .suffixes
+                    .push((suffix.to_lowercase(), parse_spec(value)));
+            } else if key == "ln" && value == "target" {
+                ls_colors.link_as_target = true;
+            } else if let Some(indicator) = Indicator::from_key(key) {
+                if is_colored(value) {
+                    ls_colors.indicators.insert(indicator, parse_spec(value));
+                } else {
+                    ls_colors.indicators.remove(&indicator);
+                }
+            }
+        }
+
+        ls_colors
+    }
+
+    /// Returns the style for a file type indicator, if it has one.
+    #[must_use]
+    pub fn style_for_indicator(
+        &self,
+        indicator: Indicator,
+    ) -> Option<StyleSpec> {
+        self.indicators.get(&indicator).copied()
+    }
+
+    /// Returns the style for a file name based on its suffix, if it has one.
+    ///
+    /// Suffixes are matched case-insensitively, and later entries take
+    /// precedence over earlier ones.
+    #[must_use]
+    pub fn style_for_file_name(&self, name: &str) -> Option<StyleSpec> {
+        let name = name.to_lowercase();
+        self.suffixes
+            .iter()
+            .rev()
+            .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .map(|&(_, spec)| spec)
+    }
+
+    /// Returns the style for `path`, based on its file type and name, the
+    /// same way `ls` picks it.
+    ///
+    /// The file system is queried for the file type and permissions.
+    #[must_use]
+    pub fn style_for_path(&self, path: &Path) -> Option<StyleSpec> {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return self.style_for_indicator(Indicator::MissingFile);
+        };
+
+        if metadata.file_type().is_symlink() {
+            return match fs::metadata(path) {
+                // Suffixes are matched against the name of the target
+                Ok(target) if self.link_as_target => {
+                    let target_path = fs::read_link(path)
+                        .unwrap_or_else(|_| path.to_path_buf());
+                    self.style_for_metadata(&target_path, &target)
+                }
+                Ok(_) => self.style_for_indicator(Indicator::Symlink),
+                Err(_) => self
+                    .style_for_indicator(Indicator::OrphanedSymlink)
+                    .or_else(|| self.style_for_indicator(Indicator::Symlink)),
+            };
+        }
+
+        self.style_for_metadata(path, &metadata)
+    }
+
+    /// Colorizes `path` with the style from [`LsColors::style_for_path`].
+    pub fn colorize_path<'a>(&self, path: &'a Path) -> ColoredString<'a> {
+        let s = ColoredString::from(path.to_string_lossy());
+        match self.style_for_path(path) {
+            Some(spec) => spec.apply(s),
+            None => s,
+        }
+    }
+
+    fn style_for_metadata(
+        &self,
+        path: &Path,
+        metadata: &Metadata,
+    ) -> Option<StyleSpec> {
+        let indicator = file_indicators(metadata)
+            .into_iter()
+            .find(|&indicator| self.indicators.contains_key(&indicator));
+
+        match indicator {
+            Some(indicator) => self.style_for_indicator(indicator),
+            // Suffixes only apply to regular files without special modes
+            None if metadata.is_file() => path
+                .file_name()
+                .and_then(|name| {
+                    self.style_for_file_name(&name.to_string_lossy())
+                })
+                .or_else(|| self.style_for_indicator(Indicator::RegularFile))
+                .or_else(|| self.style_for_indicator(Indicator::Normal)),
+            None => None,
+        }
+    }
+}
+
+/// Returns the indicators that apply to a file, most specific first.
+///
+/// Only the first one that has a color is used.
+fn file_indicators(metadata: &Metadata) -> Vec<Indicator> {
+    let file_type = metadata.file_type();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{FileTypeExt as _, MetadataExt as _};
+
+        const SETUID: u32 = 0o4000;
+        const SETGID: u32 = 0o2000;
+        const STICKY: u32 = 0o1000;
+        const OTHER_WRITABLE: u32 = 0o002;
+        const EXECUTABLE: u32 = 0o111;
+
+        let mode = metadata.mode();
+        let has = |bits: u32| mode & bits == bits;
+
+        if file_type.is_dir() {
+            let mut indicators = Vec::new();
+            if has(STICKY | OTHER_WRITABLE) {
+                indicators.push(Indicator::StickyOtherWritable);
+            }
+            if has(OTHER_WRITABLE) {
+                indicators.push(Indicator::OtherWritable);
+            }
+            if has(STICKY) {
+                indicators.push(Indicator::Sticky);
+            }
+            indicators.push(Indicator::Directory);
+            return indicators;
+        }
+
+        if file_type.is_fifo() {
+            return vec![Indicator::Fifo];
+        }
+        if file_type.is_socket() {
+            return vec![Indicator::Socket];
+        }
+        if file_type.is_block_device() {
+            return vec![Indicator::BlockDevice];
+        }
+        if file_type.is_char_device() {
+            return vec![Indicator::CharDevice];
+        }
+
+        if file_type.is_file() {
+            let mut indicators = Vec::new();
+            if has(SETUID) {
+                indicators.push(Indicator::Setuid);
+            }
+            if has(SETGID) {
+                indicators.push(Indicator::Setgid);
+            }
+            if mode & EXECUTABLE != 0 {
+                indicators.push(Indicator::Executable);
+            }
+            if metadata.nlink() > 1 {
+                indicators.push(Indicator::MultipleHardLinks);
+            }
+            return indicators;
+        }
+
+        Vec::new()
+    }
+
+    #[cfg(not(unix))]
+    {
+        if file_type.is_dir() {
+            vec![Indicator::Directory]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Returns whether an `LS_COLORS` value sets a color, the same way GNU `ls`
+/// decides it.
+fn is_colored(value: &str) -> bool {
+    !matches!(value, "" | "0" | "00")
+}
+
+/// Parses the SGR parameters of an `LS_COLORS` entry.
+fn parse_spec(params: &str) -> StyleSpec {
+    let mut state = SgrState::default();
+    state.apply(params);
+
+    StyleSpec {
+        fg: state.fgcolor,
+        bg: state.bgcolor,
+        style: state.style,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{Color, Styles};
+
+    #[test]
+    fn uncolored_indicator_falls_through_to_suffix() {
+        let dir =
+            env::temp_dir().join(format!("cnxt-ls-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let linked = dir.join("a.tar");
+        let single = dir.join("b.tar");
+        fs::write(&linked, "").unwrap();
+        fs::write(&single, "").unwrap();
+        fs::hard_link(&linked, dir.join("a-link.tar")).unwrap();
+
+        let ls_colors = LsColors::parse("mh=00:ca=00:*.tar=01;31");
+        let linked_style = ls_colors.style_for_path(&linked);
+        let single_style = ls_colors.style_for_path(&single);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            ls_colors.style_for_indicator(Indicator::MultipleHardLinks),
+            None
+        );
+        for style in [linked_style, single_style] {
+            let style = style.unwrap();
+            assert_eq!(style.fg, Some(Color::Red));
+            assert!(style.style.contains(Styles::Bold));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Format strings with inline markup, such as `"<red>error</red>: {}"`.
+
+use core::fmt;
+
+use crate::{
+    Color, ColoredString, Style, Styles, control::ColorLevel,
+    styled_text::Rendition,
+};
+
+/// Creates a `String` like [`format!`], with colors and styles written as
+/// tags in the format string.
+///
+/// A tag holds style names, a foreground color and `on` followed by a
+/// background color, e.g. `<bold red>` or `<italic bright blue on black>`.
+/// `on_red` is short for `on red`. The style names are those of
+/// [`Styles`], and colors are the 16 standard names, the CSS color names,
+/// `#rgb`, `#rrggbb` and `ansi256(n)`. Unlike a theme spec, a tag can't
+/// hold color functions such as `rgb(…)`.
+///
+/// A tag is closed by `</>`, or by repeating its words as in `</bold red>`.
+/// Tags nest, and tags that are still open end with the string. Write `<<`
+/// for a literal `<`.
+///
+/// The tags are checked at compile time, so a typo such as `<rde>` or a
+/// mismatched closing tag is a compile error. Arguments are interpolated as
+/// with [`format!`], and their text is never parsed as markup. Tags can't
+/// be written in braces, as in `{red error}`, because braces already
+/// delimit the arguments.
+///
+/// The escape codes follow the color level of stdout, see
+/// [`get_color_level_for()`](crate::control::get_color_level_for). Like
+/// [`StyledText`](crate::StyledText), only the parameters that change at a
+/// tag are written.
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// use cnxt::{
+///     cformat,
+///     control::{ColorLevel, with_color_level},
+/// };
+///
+/// let msg = "disk full";
+/// let line = with_color_level(ColorLevel::Ansi16, || {
+///     cformat!("<bold red>error</>: <italic>{msg}</italic> ({:<3}%)", 99)
+/// });
+/// assert_eq!(line, "\x1B[1;31merror\x1B[0m: \x1B[3mdisk full\x1B[0m (99 %)");
+///
+/// let plain = with_color_level(ColorLevel::None, || cformat!("<<<green>ok</>>"));
+/// assert_eq!(plain, "<ok>");
+///
+/// let css = with_color_level(ColorLevel::TrueColor, || cformat!("<orange>!"));
+/// assert_eq!(css, "\x1B[38;2;255;165;0m!\x1B[0m");
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// let line = cnxt::cformat!("<rde>typo</rde>");
+/// ```
+#[macro_export]
+macro_rules! cformat {
+    ($($arg:tt)*) => {
+        $crate::__private::format_markup!($crate, format, $($arg)*)
+    };
+}
+
+/// Prints to stdout like [`print!`], with markup in the format string.
+///
+/// See [`cformat!`] for the markup.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! cprint {
+    ($($arg:tt)*) => {
+        $crate::__private::format_markup!($crate, print, $($arg)*)
+    };
+}
+
+/// Prints to stdout like [`println!`], with markup in the format string.
+///
+/// See [`cformat!`] for the markup.
+///
+/// ```rust
+/// use cnxt::cprintln;
+///
+/// cprintln!("<green>✓</> {} tests passed", 42);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! cprintln {
+    ($($arg:tt)*) => {
+        $crate::__private::format_markup!($crate, println, $($arg)*)
+    };
+}
+
+/// Prints to stderr like [`eprint!`], with markup in the format string.
+///
+/// The escape codes follow the color level of stderr. See [`cformat!`] for
+/// the markup.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! ceprint {
+    ($($arg:tt)*) => {
+        $crate::__private::format_markup!($crate, eprint, $($arg)*)
+    };
+}
+
+/// Prints to stderr like [`eprintln!`], with markup in the format string.
+///
+/// The escape codes follow the color level of stderr. See [`cformat!`] for
+/// the markup.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! ceprintln {
+    ($($arg:tt)*) => {
+        $crate::__private::format_markup!($crate, eprintln, $($arg)*)
+    };
+}
+
+/// The colors and styles in effect at some point of a markup string.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct MarkupState {
+    pub styles: &'static [Styles],
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+impl MarkupState {
+    fn rendition(&self, level: ColorLevel) -> Rendition {
+        let span = ColoredString {
+            fgcolor: self.fg,
+            bgcolor: self.bg,
+            style: self.styles.iter().copied().collect::<Style>(),
+            ..ColoredString::default()
+        };
+        Rendition::of(&span, level)
+    }
+}
+
+/// Displays the escape codes of a tag of a markup string.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct Markup {
+    level: ColorLevel,
+    from: MarkupState,
+    to: MarkupState,
+}
+
+impl Markup {
+    pub fn new(level: ColorLevel, from: MarkupState, to: MarkupState) -> Self {
+        Self { level, from, to }
+    }
+}
+
+impl fmt::Display for Markup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.level == ColorLevel::None {
+            return Ok(());
+        }
+
+        let from = self.from.rendition(self.level);
+        let to = self.to.rendition(self.level);
+        match from.transition_to(&to) {
+            Some(params) => write!(f, "\x1B[{params}m"),
+            None => Ok(()),
+        }
+    }
+}
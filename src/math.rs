@@ -0,0 +1,70 @@
+//! The `f32` functions that need `std`, with a fallback to `libm` for
+//! `no_std` builds.
+//!
+//! These are free functions rather than a trait, so that the inherent
+//! methods of `std` never shadow them when some other crate links `std`.
+
+macro_rules! forward {
+    ($($name:ident($($arg:ident),*) => $libm:ident;)*) => {$(
+        #[inline]
+        pub(crate) fn $name(x: f32 $(, $arg: f32)*) -> f32 {
+            #[cfg(feature = "std")]
+            {
+                x.$name($($arg),*)
+            }
+            #[cfg(not(feature = "std"))]
+            {
+                libm::$libm(x $(, $arg)*)
+            }
+        }
+    )*};
+}
+
+forward! {
+    powf(n) => powf;
+    sqrt() => sqrtf;
+    cbrt() => cbrtf;
+    hypot(other) => hypotf;
+    exp() => expf;
+    sin() => sinf;
+    cos() => cosf;
+    atan2(other) => atan2f;
+    round() => roundf;
+}
+
+#[inline]
+pub(crate) fn powi(x: f32, n: i32) -> f32 {
+    #[cfg(feature = "std")]
+    {
+        x.powi(n)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        libm::powf(x, n as f32)
+    }
+}
+
+#[inline]
+pub(crate) fn sin_cos(x: f32) -> (f32, f32) {
+    #[cfg(feature = "std")]
+    {
+        x.sin_cos()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        libm::sincosf(x)
+    }
+}
+
+#[inline]
+pub(crate) fn rem_euclid(x: f32, rhs: f32) -> f32 {
+    #[cfg(feature = "std")]
+    {
+        x.rem_euclid(rhs)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let r = x % rhs;
+        if r < 0.0 { r + rhs.abs() } else { r }
+    }
+}
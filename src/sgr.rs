@@ -0,0 +1,292 @@
+//! Parsing of SGR (Select Graphic Rendition) escape sequences.
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use crate::{AnsiSegment, AnsiSegments, Color, ColoredString, Style, Styles};
+
+/// Splits text containing SGR escape sequences into [`ColoredString`]s.
+///
+/// Every run of text is returned with the colors and styles that are in
+/// effect for it, so re-displaying the result reproduces the original
+/// appearance. This understands the codes that this crate emits, as well as
+/// the `;` and `:` forms of 256-color and RGB colors, underline styles and
+/// colors, resets and the codes that turn single attributes off. Other
+/// escape sequences are dropped.
+///
+/// Text is borrowed from `s` where possible.
+///
+/// ```rust
+/// use cnxt::{Color, Styles, parse_ansi};
+///
+/// let spans = parse_ansi("\x1B[1;31merror\x1B[0m: \x1B[38;5;208mdisk full\x1B[0m");
+///
+/// assert_eq!(spans.len(), 3);
+/// assert_eq!(&*spans[0], "error");
+/// assert_eq!(spans[0].fgcolor, Some(Color::Red));
+/// assert!(spans[0].style.contains(Styles::Bold));
+/// assert!(spans[1].is_plain());
+/// assert_eq!(spans[2].fgcolor, Some(Color::Ansi256 { idx: 208 }));
+///
+/// let spans = parse_ansi("\x1B[4:3;58:2::255:0:0msquiggly\x1B[0m");
+/// assert!(spans[0].style.contains(Styles::CurlyUnderline));
+/// assert_eq!(spans[0].underline_color, Some(Color::TrueColor { r: 255, g: 0, b: 0 }));
+/// ```
+#[must_use]
+pub fn parse_ansi(s: &str) -> Vec<ColoredString<'_>> {
+    let mut spans: Vec<ColoredString> = Vec::new();
+    let mut state = SgrState::default();
+
+    for segment in AnsiSegments::new(s) {
+        match segment {
+            AnsiSegment::Text(text) => match spans.last_mut() {
+                Some(last) if SgrState::of(last) == state => {
+                    last.input.to_mut().push_str(text);
+                }
+                _ => spans.push(state.to_colored_string(text)),
+            },
+            AnsiSegment::Escape(seq) => {
+                if let Some(params) = sgr_params(seq) {
+                    state.apply(params);
+                }
+            }
+        }
+    }
+
+    spans
+}
+
+/// Returns the parameters of `seq` if it is an SGR sequence.
+fn sgr_params(seq: &str) -> Option<&str> {
+    let params = seq
+        .strip_prefix("\x1B[")
+        .or_else(|| seq.strip_prefix('\u{9b}'))?
+        .strip_suffix('m')?;
+
+    params
+        .bytes()
+        .all(|b| b.is_ascii_digit() || b == b';' || b == b':')
+        .then_some(params)
+}
+
+/// The graphic rendition in effect at some point of a text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SgrState {
+    pub(crate) fgcolor: Option<Color>,
+    pub(crate) bgcolor: Option<Color>,
+    pub(crate) style: Style,
+    pub(crate) underline_color: Option<Color>,
+}
+
+impl SgrState {
+    fn of(s: &ColoredString) -> Self {
+        Self {
+            fgcolor: s.fgcolor,
+            bgcolor: s.bgcolor,
+            style: s.style,
+            underline_color: s.underline_color,
+        }
+    }
+
+    fn to_colored_string<'a>(
+        self,
+        input: impl Into<Cow<'a, str>>,
+    ) -> ColoredString<'a> {
+        ColoredString {
+            input: input.into(),
+            fgcolor: self.fgcolor,
+            bgcolor: self.bgcolor,
+            style: self.style,
+            underline_color: self.underline_color,
+            link: None,
+        }
+    }
+
+    /// Applies the `;`-separated SGR parameters in `params`.
+    ///
+    /// Unknown or malformed parameters are ignored.
+    pub(crate) fn apply(&mut self, params: &str) {
+        let mut params = params.split(';');
+
+        while let Some(param) = params.next() {
+            if param.contains(':') {
+                self.apply_subparams(param);
+                continue;
+            }
+
+            let Some(code) = parse_param(param) else {
+                continue;
+            };
+            match code {
+                38 | 48 | 58 => {
+                    let color = match params.next().and_then(parse_param) {
+                        Some(5) => params
+                            .next()
+                            .and_then(parse_param)
+                            .and_then(|idx| u8::try_from(idx).ok())
+                            .map(|idx| Color::Ansi256 { idx }),
+                        Some(2) => {
+                            let mut channel = || {
+                                params
+                                    .next()
+                                    .and_then(parse_param)
+                                    .and_then(|c| u8::try_from(c).ok())
+                            };
+                            match (channel(), channel(), channel()) {
+                                (Some(r), Some(g), Some(b)) => {
+                                    Some(Color::TrueColor { r, g, b })
+                                }
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    };
+                    self.set_color(code, color);
+                }
+                code => self.apply_code(code),
+            }
+        }
+    }
+
+    /// Applies a single parameter in the `:` form, e.g. `38:2::255:128:0`.
+    fn apply_subparams(&mut self, param: &str) {
+        let mut subparams = param.split(':');
+        let code = subparams.next().and_then(parse_param);
+        let rest: Vec<&str> = subparams.collect();
+
+        match code {
+            Some(code @ (38 | 48 | 58)) => {
+                let to_u8 =
+                    |s: &str| parse_param(s).and_then(|v| u8::try_from(v).ok());
+                let color = match rest.as_slice() {
+                    ["5", idx] => to_u8(idx).map(|idx| Color::Ansi256 { idx }),
+                    // With and without the color space identifier
+                    ["2", _, r, g, b, ..] | ["2", r, g, b] => {
+                        match (to_u8(r), to_u8(g), to_u8(b)) {
+                            (Some(r), Some(g), Some(b)) => {
+                                Some(Color::TrueColor { r, g, b })
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                self.set_color(code, color);
+            }
+            Some(4) => {
+                let style = match rest.first().and_then(|s| parse_param(s)) {
+                    Some(0) => {
+                        self.style.remove_underlines();
+                        return;
+                    }
+                    Some(2) => Styles::DoubleUnderline,
+                    Some(3) => Styles::CurlyUnderline,
+                    Some(4) => Styles::DottedUnderline,
+                    Some(5) => Styles::DashedUnderline,
+                    Some(_) => Styles::Underline,
+                    None => return,
+                };
+                self.style.set_underline(style);
+            }
+            _ => {}
+        }
+    }
+
+    fn set_color(&mut self, code: u16, color: Option<Color>) {
+        if color.is_none() {
+            return;
+        }
+
+        match code {
+            38 => self.fgcolor = color,
+            48 => self.bgcolor = color,
+            _ => self.underline_color = color,
+        }
+    }
+
+    fn apply_code(&mut self, code: u16) {
+        const BASIC: [Color; 8] = [
+            Color::Black,
+            Color::Red,
+            Color::Green,
+            Color::Yellow,
+            Color::Blue,
+            Color::Magenta,
+            Color::Cyan,
+            Color::White,
+        ];
+        const BRIGHT: [Color; 8] = [
+            Color::BrightBlack,
+            Color::BrightRed,
+            Color::BrightGreen,
+            Color::BrightYellow,
+            Color::BrightBlue,
+            Color::BrightMagenta,
+            Color::BrightCyan,
+            Color::BrightWhite,
+        ];
+
+        match code {
+            0 => *self = Self::default(),
+            1 => self.style.add(Styles::Bold),
+            2 => self.style.add(Styles::Dimmed),
+            3 => self.style.add(Styles::Italic),
+            4 => self.style.set_underline(Styles::Underline),
+            5 => self.style.add(Styles::Blink),
+            6 => self.style.add(Styles::RapidBlink),
+            7 => self.style.add(Styles::Reversed),
+            8 => self.style.add(Styles::Hidden),
+            9 => self.style.add(Styles::Strikethrough),
+            21 => self.style.set_underline(Styles::DoubleUnderline),
+            22 => {
+                self.style.remove(Styles::Bold);
+                self.style.remove(Styles::Dimmed);
+            }
+            23 => self.style.remove(Styles::Italic),
+            24 => self.style.remove_underlines(),
+            25 => {
+                self.style.remove(Styles::Blink);
+                self.style.remove(Styles::RapidBlink);
+            }
+            27 => self.style.remove(Styles::Reversed),
+            28 => self.style.remove(Styles::Hidden),
+            29 => self.style.remove(Styles::Strikethrough),
+            30..=37 => self.fgcolor = Some(BASIC[usize::from(code - 30)]),
+            39 => self.fgcolor = None,
+            40..=47 => self.bgcolor = Some(BASIC[usize::from(code - 40)]),
+            49 => self.bgcolor = None,
+            51 => self.style.add(Styles::Framed),
+            52 => self.style.add(Styles::Encircled),
+            53 => self.style.add(Styles::Overline),
+            54 => {
+                self.style.remove(Styles::Framed);
+                self.style.remove(Styles::Encircled);
+            }
+            55 => self.style.remove(Styles::Overline),
+            59 => self.underline_color = None,
+            73 => {
+                self.style.remove(Styles::Subscript);
+                self.style.add(Styles::Superscript);
+            }
+            74 => {
+                self.style.remove(Styles::Superscript);
+                self.style.add(Styles::Subscript);
+            }
+            75 => {
+                self.style.remove(Styles::Superscript);
+                self.style.remove(Styles::Subscript);
+            }
+            90..=97 => self.fgcolor = Some(BRIGHT[usize::from(code - 90)]),
+            100..=107 => self.bgcolor = Some(BRIGHT[usize::from(code - 100)]),
+            _ => {}
+        }
+    }
+}
+
+/// Parses a single numeric parameter, where an empty parameter means 0.
+fn parse_param(param: &str) -> Option<u16> {
+    if param.is_empty() {
+        Some(0)
+    } else {
+        param.parse().ok()
+    }
+}
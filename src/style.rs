@@ -1,5 +1,11 @@
-use core::ops::{
-    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not,
+use alloc::{string::String, vec::Vec};
+use core::{
+    error::Error,
+    fmt,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not,
+    },
+    str::FromStr,
 };
 
 macro_rules! auto_impl_ref_binop_trait {
@@ -60,7 +66,7 @@ macro_rules! impl_binary_op_for_styles {
 
             #[inline]
             fn $method(self, rhs: Self) -> Self::Output {
-                Style(self.to_u8() $op rhs.to_u8())
+                Style(self.to_u32() $op rhs.to_u32())
             }
         }
 
@@ -71,7 +77,7 @@ macro_rules! impl_binary_op_for_styles {
 
             #[inline]
             fn $method(self, rhs: Style) -> Self::Output {
-                Style(self.to_u8() $op rhs.0)
+                Style(self.to_u32() $op rhs.0)
             }
         }
 
@@ -97,7 +103,7 @@ macro_rules! impl_binary_op_for_style {
 
             #[inline]
             fn $method(self, rhs: Styles) -> Self::Output {
-                Self(self.0 $op rhs.to_u8())
+                Self(self.0 $op rhs.to_u32())
             }
         }
 
@@ -112,32 +118,71 @@ macro_rules! impl_style_method {
         /// Returns the modified Style for chaining.
         #[must_use]
         #[inline]
-        pub fn $name(mut self) -> Self {
+        pub const fn $name(mut self) -> Self {
             self.add(Styles::$style);
             self
         }
     };
+    ($name:ident, $style:ident, underline) => {
+        /// Switches this Style to the specified underline style, replacing
+        /// any other underline style.
+        ///
+        /// Returns the modified Style for chaining.
+        #[must_use]
+        #[inline]
+        pub const fn $name(mut self) -> Self {
+            self.set_underline(Styles::$style);
+            self
+        }
+    };
 }
 
-const CLEARV: u8 = 0b0000_0000;
-const BOLD: u8 = 0b0000_0001;
-const UNDERLINE: u8 = 0b0000_0010;
-const REVERSED: u8 = 0b0000_0100;
-const ITALIC: u8 = 0b0000_1000;
-const BLINK: u8 = 0b0001_0000;
-const HIDDEN: u8 = 0b0010_0000;
-const DIMMED: u8 = 0b0100_0000;
-const STRIKETHROUGH: u8 = 0b1000_0000;
-
-static STYLES: [(u8, Styles); 8] = [
+const CLEARV: u32 = 0;
+const BOLD: u32 = 1 << 0;
+const UNDERLINE: u32 = 1 << 1;
+const REVERSED: u32 = 1 << 2;
+const ITALIC: u32 = 1 << 3;
+const BLINK: u32 = 1 << 4;
+const HIDDEN: u32 = 1 << 5;
+const DIMMED: u32 = 1 << 6;
+const STRIKETHROUGH: u32 = 1 << 7;
+const CURLY_UNDERLINE: u32 = 1 << 8;
+const DOUBLE_UNDERLINE: u32 = 1 << 9;
+const DOTTED_UNDERLINE: u32 = 1 << 10;
+const DASHED_UNDERLINE: u32 = 1 << 11;
+const OVERLINE: u32 = 1 << 12;
+const RAPID_BLINK: u32 = 1 << 13;
+const FRAMED: u32 = 1 << 14;
+const ENCIRCLED: u32 = 1 << 15;
+const SUPERSCRIPT: u32 = 1 << 16;
+const SUBSCRIPT: u32 = 1 << 17;
+
+/// Every underline style, which are mutually exclusive on a terminal.
+const UNDERLINES: u32 = UNDERLINE
+    | CURLY_UNDERLINE
+    | DOUBLE_UNDERLINE
+    | DOTTED_UNDERLINE
+    | DASHED_UNDERLINE;
+
+static STYLES: [(u32, Styles); 18] = [
     (BOLD, Styles::Bold),
     (DIMMED, Styles::Dimmed),
     (UNDERLINE, Styles::Underline),
+    (CURLY_UNDERLINE, Styles::CurlyUnderline),
+    (DOUBLE_UNDERLINE, Styles::DoubleUnderline),
+    (DOTTED_UNDERLINE, Styles::DottedUnderline),
+    (DASHED_UNDERLINE, Styles::DashedUnderline),
     (REVERSED, Styles::Reversed),
     (ITALIC, Styles::Italic),
     (BLINK, Styles::Blink),
     (HIDDEN, Styles::Hidden),
     (STRIKETHROUGH, Styles::Strikethrough),
+    (OVERLINE, Styles::Overline),
+    (RAPID_BLINK, Styles::RapidBlink),
+    (FRAMED, Styles::Framed),
+    (ENCIRCLED, Styles::Encircled),
+    (SUPERSCRIPT, Styles::Superscript),
+    (SUBSCRIPT, Styles::Subscript),
 ];
 
 pub static CLEAR: Style = Style(CLEARV);
@@ -150,7 +195,7 @@ pub static CLEAR: Style = Style(CLEARV);
 ///
 /// - Default (no styles): `Style::default()`
 /// - From individual style: `Style::from(Styles::Bold)`
-/// - Builder pattern: `Style::default().bold().italic()`
+/// - Builder pattern: `Style::new().bold().italic()`, also in a `const`
 /// - From multiple styles: `Style::from_iter([Styles::Bold, Styles::Italic])`
 ///
 /// ## Combining Styles with Operators
@@ -182,8 +227,21 @@ pub static CLEAR: Style = Style(CLEARV);
 /// assert!(!style.contains(Styles::Bold));
 /// assert!(style.contains(Styles::Underline));
 /// ```
+///
+/// ## Underline Styles
+///
+/// A terminal shows one underline style at a time, so the builder methods
+/// of the underline styles replace each other:
+///
+/// ```rust
+/// use cnxt::*;
+///
+/// let style = Style::new().underline().curly_underline();
+/// assert!(style.contains(Styles::CurlyUnderline));
+/// assert!(!style.contains(Styles::Underline));
+/// ```
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub struct Style(u8);
+pub struct Style(u32);
 
 /// Individual style flags that can be applied to text.
 ///
@@ -206,6 +264,12 @@ pub struct Style(u8);
 /// let not_bold = !Styles::Bold;
 /// assert!(!not_bold.contains(Styles::Bold));
 /// assert!(not_bold.contains(Styles::Underline));
+///
+/// // Less common attributes are supported as well
+/// let heading = Styles::Overline | Styles::Framed;
+/// assert!(heading.contains(Styles::Overline));
+/// assert!(!heading.contains(Styles::Encircled));
+/// assert!((!Styles::Subscript).contains(Styles::Superscript));
 /// ```
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[allow(missing_docs)]
@@ -219,6 +283,30 @@ pub enum Styles {
     Blink,
     Hidden,
     Strikethrough,
+    /// A wavy underline, `4:3`. Often used to mark errors.
+    CurlyUnderline,
+    /// A double underline, written as `4:2`.
+    ///
+    /// SGR `21` is not written, because some terminals read it as "bold
+    /// off" instead. [`parse_ansi`](crate::parse_ansi) still reads `21` as a
+    /// double underline.
+    DoubleUnderline,
+    /// A dotted underline, `4:4`.
+    DottedUnderline,
+    /// A dashed underline, `4:5`.
+    DashedUnderline,
+    /// An overline, `53`.
+    Overline,
+    /// Fast blinking, `6`.
+    RapidBlink,
+    /// A frame around the text, `51`.
+    Framed,
+    /// A circle around the text, `52`.
+    Encircled,
+    /// Superscript text, `73`.
+    Superscript,
+    /// Subscript text, `74`.
+    Subscript,
 }
 
 impl Styles {
@@ -234,11 +322,45 @@ impl Styles {
             Self::Reversed => "7",
             Self::Hidden => "8",
             Self::Strikethrough => "9",
+            Self::CurlyUnderline => "4:3",
+            Self::DoubleUnderline => "4:2",
+            Self::DottedUnderline => "4:4",
+            Self::DashedUnderline => "4:5",
+            Self::Overline => "53",
+            Self::RapidBlink => "6",
+            Self::Framed => "51",
+            Self::Encircled => "52",
+            Self::Superscript => "73",
+            Self::Subscript => "74",
+        }
+    }
+
+    /// Returns the SGR parameter that turns this style off, and every style
+    /// that parameter turns off along with it.
+    fn to_off_str<'a>(self) -> (&'a str, u32) {
+        match self {
+            Self::Clear => ("", CLEARV),
+            Self::Bold | Self::Dimmed => ("22", BOLD | DIMMED),
+            Self::Italic => ("23", ITALIC),
+            Self::Underline
+            | Self::CurlyUnderline
+            | Self::DoubleUnderline
+            | Self::DottedUnderline
+            | Self::DashedUnderline => ("24", UNDERLINES),
+            Self::Blink | Self::RapidBlink => ("25", BLINK | RAPID_BLINK),
+            Self::Reversed => ("27", REVERSED),
+            Self::Hidden => ("28", HIDDEN),
+            Self::Strikethrough => ("29", STRIKETHROUGH),
+            Self::Framed | Self::Encircled => ("54", FRAMED | ENCIRCLED),
+            Self::Overline => ("55", OVERLINE),
+            Self::Superscript | Self::Subscript => {
+                ("75", SUPERSCRIPT | SUBSCRIPT)
+            }
         }
     }
 
     #[inline]
-    const fn to_u8(self) -> u8 {
+    const fn to_u32(self) -> u32 {
         match self {
             Self::Clear => CLEARV,
             Self::Bold => BOLD,
@@ -249,10 +371,20 @@ impl Styles {
             Self::Reversed => REVERSED,
             Self::Hidden => HIDDEN,
             Self::Strikethrough => STRIKETHROUGH,
+            Self::CurlyUnderline => CURLY_UNDERLINE,
+            Self::DoubleUnderline => DOUBLE_UNDERLINE,
+            Self::DottedUnderline => DOTTED_UNDERLINE,
+            Self::DashedUnderline => DASHED_UNDERLINE,
+            Self::Overline => OVERLINE,
+            Self::RapidBlink => RAPID_BLINK,
+            Self::Framed => FRAMED,
+            Self::Encircled => ENCIRCLED,
+            Self::Superscript => SUPERSCRIPT,
+            Self::Subscript => SUBSCRIPT,
         }
     }
 
-    fn from_u8(u: u8) -> Option<Vec<Self>> {
+    fn from_u32(u: u32) -> Option<Vec<Self>> {
         if u == CLEARV {
             return None;
         }
@@ -266,6 +398,151 @@ impl Styles {
     }
 }
 
+impl fmt::Display for Styles {
+    /// Formats the style as its name in snake case, e.g. `bold` or
+    /// `curly_underline`, which [`Styles::from_str`] parses back.
+    ///
+    /// [`Styles::from_str`]: #method.from_str
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Clear => "clear",
+            Self::Bold => "bold",
+            Self::Dimmed => "dimmed",
+            Self::Underline => "underline",
+            Self::Reversed => "reversed",
+            Self::Italic => "italic",
+            Self::Blink => "blink",
+            Self::Hidden => "hidden",
+            Self::Strikethrough => "strikethrough",
+            Self::CurlyUnderline => "curly_underline",
+            Self::DoubleUnderline => "double_underline",
+            Self::DottedUnderline => "dotted_underline",
+            Self::DashedUnderline => "dashed_underline",
+            Self::Overline => "overline",
+            Self::RapidBlink => "rapid_blink",
+            Self::Framed => "framed",
+            Self::Encircled => "encircled",
+            Self::Superscript => "superscript",
+            Self::Subscript => "subscript",
+        })
+    }
+}
+
+/// Serialized as its name, see the [`Display`](fmt::Display)
+/// implementation.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Styles {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Styles {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serialized as a list of style names, e.g. `["bold", "underline"]`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Style {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(Styles::from_u32(self.0).unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Style {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let styles = Vec::<Styles>::deserialize(deserializer)?;
+        Ok(styles
+            .into_iter()
+            .fold(Self::default(), |style, s| style | s))
+    }
+}
+
+impl FromStr for Styles {
+    type Err = ParseStyleError;
+
+    /// Parses a style from its name.
+    ///
+    /// Names are case-insensitive and may use spaces, `_` or `-` as
+    /// separators. `dim`, `blink` and `strike` are accepted as short forms,
+    /// as well as `curly`, `double`, `dotted` and `dashed` for the extended
+    /// underlines.
+    ///
+    /// ```rust
+    /// use cnxt::Styles;
+    ///
+    /// assert_eq!("bold".parse(), Ok(Styles::Bold));
+    /// assert_eq!("Curly-Underline".parse(), Ok(Styles::CurlyUnderline));
+    /// assert_eq!("dim".parse(), Ok(Styles::Dimmed));
+    /// assert!("shiny".parse::<Styles>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        if name.is_empty() {
+            return Err(ParseStyleError::Empty);
+        }
+
+        style_named(&name).ok_or(ParseStyleError::UnknownName)
+    }
+}
+
+macro_rules! style_names {
+    ($($($name:literal)|+ => $variant:ident,)*) => {
+        /// Returns the style called `name`, which is lowercase and without
+        /// separators.
+        fn style_named(name: &str) -> Option<Styles> {
+            match name {
+                $($($name)|+ => Some(Styles::$variant),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+cnxt_shared::style_names!(style_names);
+
+/// An error which can be returned when parsing a [`Styles`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseStyleError {
+    /// The string was empty.
+    Empty,
+    /// The string is not a known style name.
+    UnknownName,
+}
+
+impl fmt::Display for ParseStyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Empty => "cannot parse style from empty string",
+            Self::UnknownName => "unknown style name",
+        })
+    }
+}
+
+impl Error for ParseStyleError {}
+
 // Using our binary operation macros for Styles
 impl_binary_op_for_styles!(impl BitAnd, bitand, & for Styles);
 impl_binary_op_for_styles!(impl BitOr, bitor, | for Styles);
@@ -276,7 +553,7 @@ impl Not for Styles {
 
     #[inline]
     fn not(self) -> Self::Output {
-        Style(!self.to_u8())
+        Style(!self.to_u32())
     }
 }
 
@@ -285,21 +562,38 @@ impl Not for &Styles {
 
     #[inline]
     fn not(self) -> Self::Output {
-        Style(!self.to_u8())
+        Style(!self.to_u32())
     }
 }
 
 impl Style {
+    /// Creates a style without any attributes.
+    ///
+    /// Unlike [`Style::default`], this can be used in a `const`, together
+    /// with the builder methods:
+    ///
+    /// ```rust
+    /// use cnxt::{Style, Styles};
+    ///
+    /// const HEADING: Style = Style::new().bold().underline();
+    /// assert!(HEADING.contains(Styles::Bold));
+    /// ```
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self(CLEARV)
+    }
+
     #[must_use]
     #[inline]
-    pub fn contains(self, style: Styles) -> bool {
-        let s = style.to_u8();
+    pub const fn contains(self, style: Styles) -> bool {
+        let s = style.to_u32();
         self.0 & s == s
     }
 
     #[inline]
     pub(crate) fn to_str(self) -> String {
-        match Styles::from_u8(self.0) {
+        match Styles::from_u32(self.0) {
             Some(styles) => styles
                 .iter()
                 .map(|s| s.to_str())
@@ -310,24 +604,87 @@ impl Style {
     }
 
     #[inline]
-    pub fn add(&mut self, style: Styles) {
-        self.0 |= style.to_u8();
+    pub const fn add(&mut self, style: Styles) {
+        self.0 |= style.to_u32();
+    }
+
+    #[inline]
+    pub const fn remove(&mut self, style: Styles) {
+        self.0 &= !style.to_u32();
+    }
+
+    /// Returns the SGR parameters that change the styles in effect from
+    /// `self` to `target`, without resetting everything else.
+    ///
+    /// Some parameters turn off several styles at once, e.g. `22` turns off
+    /// both bold and dimmed, so styles of `target` that get turned off this
+    /// way are turned on again.
+    pub(crate) fn transition_str(self, target: Self) -> String {
+        let mut params: Vec<&str> = Vec::new();
+        let mut turned_off = CLEARV;
+
+        for &(mask, style) in &STYLES {
+            if self.0 & mask != 0 && target.0 & mask == 0 {
+                let (param, clears) = style.to_off_str();
+                if !params.contains(&param) {
+                    params.push(param);
+                    turned_off |= clears;
+                }
+            }
+        }
+
+        let turned_on = Self(target.0 & !(self.0 & !turned_off)).to_str();
+        if !turned_on.is_empty() {
+            params.push(&turned_on);
+        }
+
+        params.join(";")
+    }
+
+    /// Removes the plain and all extended underline styles.
+    #[inline]
+    pub(crate) const fn remove_underlines(&mut self) {
+        self.0 &= !UNDERLINES;
     }
 
+    /// Switches to the underline style `style`, replacing any other one,
+    /// since a terminal shows only one of them.
     #[inline]
-    pub fn remove(&mut self, style: Styles) {
-        self.0 &= !style.to_u8();
+    pub(crate) const fn set_underline(&mut self, style: Styles) {
+        self.remove_underlines();
+        self.add(style);
+    }
+
+    /// Adds the styles of `other`, whose underline style replaces the one
+    /// of `self`, if any.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub(crate) const fn merge(&mut self, other: Self) {
+        if other.0 & UNDERLINES != 0 {
+            self.remove_underlines();
+        }
+        self.0 |= other.0;
     }
 
     // Using our style method macro for all style methods
     impl_style_method!(bold, Bold);
     impl_style_method!(dimmed, Dimmed);
-    impl_style_method!(underline, Underline);
+    impl_style_method!(underline, Underline, underline);
     impl_style_method!(reversed, Reversed);
     impl_style_method!(italic, Italic);
     impl_style_method!(blink, Blink);
     impl_style_method!(hidden, Hidden);
     impl_style_method!(strikethrough, Strikethrough);
+    impl_style_method!(curly_underline, CurlyUnderline, underline);
+    impl_style_method!(double_underline, DoubleUnderline, underline);
+    impl_style_method!(dotted_underline, DottedUnderline, underline);
+    impl_style_method!(dashed_underline, DashedUnderline, underline);
+    impl_style_method!(overline, Overline);
+    impl_style_method!(rapid_blink, RapidBlink);
+    impl_style_method!(framed, Framed);
+    impl_style_method!(encircled, Encircled);
+    impl_style_method!(superscript, Superscript);
+    impl_style_method!(subscript, Subscript);
 }
 
 // Using our binary operation macros for Style
@@ -369,14 +726,14 @@ impl Default for Style {
 impl From<Styles> for Style {
     #[inline]
     fn from(value: Styles) -> Self {
-        Self(value.to_u8())
+        Self(value.to_u32())
     }
 }
 
 impl From<&Styles> for Style {
     #[inline]
     fn from(value: &Styles) -> Self {
-        Self(value.to_u8())
+        Self(value.to_u32())
     }
 }
 
@@ -0,0 +1,307 @@
+//! A sequence of spans that is rendered with as few escape codes as
+//! possible.
+
+use alloc::{
+    borrow::{Cow, ToOwned as _},
+    format,
+    string::String,
+    vec::Vec,
+};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::{
+    ColoredString, Hyperlink, Style,
+    control::{self, ColorLevel},
+};
+
+/// A line or block of text made of [`ColoredString`] spans.
+///
+/// Displaying a `StyledText` gives the same result as displaying each span
+/// in turn, but only the SGR parameters that change between adjacent spans
+/// are written, and the styles are reset once at the end instead of after
+/// every span. This makes a large difference for output like images drawn
+/// with block characters, where neighboring cells often share colors.
+///
+/// ```rust
+/// use cnxt::{Colorize as _, StyledText, control::ColorLevel};
+///
+/// let mut text = StyledText::new();
+/// text.push("error".red().bold());
+/// text.push(": ".bold());
+/// text.push("disk full");
+///
+/// assert_eq!(
+///     text.render(ColorLevel::Ansi16),
+///     "\x1B[1;31merror\x1B[39m: \x1B[0mdisk full"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StyledText<'a> {
+    spans: Vec<ColoredString<'a>>,
+}
+
+impl<'a> StyledText<'a> {
+    /// Creates an empty `StyledText`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty `StyledText` with space for `capacity` spans.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            spans: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Appends a span.
+    pub fn push(&mut self, span: impl Into<ColoredString<'a>>) {
+        self.spans.push(span.into());
+    }
+
+    /// Returns the spans.
+    #[must_use]
+    pub fn spans(&self) -> &[ColoredString<'a>] {
+        &self.spans
+    }
+
+    /// Returns the spans for modification.
+    pub fn spans_mut(&mut self) -> &mut Vec<ColoredString<'a>> {
+        &mut self.spans
+    }
+
+    /// Returns the spans, consuming the `StyledText`.
+    #[must_use]
+    pub fn into_spans(self) -> Vec<ColoredString<'a>> {
+        self.spans
+    }
+
+    /// Returns the number of spans.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns true if there are no spans.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Returns a value that displays this `StyledText` at the given color
+    /// level, ignoring the global and thread-local settings.
+    ///
+    /// See [`ColoredString::display_at`] for details.
+    ///
+    /// ```rust
+    /// use cnxt::{Colorize as _, StyledText, control::ColorLevel};
+    ///
+    /// let text = StyledText::from(vec!["docs".link("https://docs.rs/cnxt")]);
+    /// let plain = text.display_at(ColorLevel::Ansi16).hyperlinks(false);
+    /// assert_eq!(plain.to_string(), "docs");
+    /// ```
+    #[must_use]
+    pub fn display_at(&self, level: ColorLevel) -> StyledDisplay<'_> {
+        StyledDisplay {
+            inner: self,
+            level,
+            hyperlinks: true,
+        }
+    }
+
+    /// Renders this `StyledText` at the given color level.
+    ///
+    /// See [`ColoredString::render`] for details.
+    #[must_use]
+    pub fn render(&self, level: ColorLevel) -> String {
+        let mut out = String::new();
+        self.fmt_at(&mut out, level, true)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    /// Writes this `StyledText` to `writer` at the given color level.
+    ///
+    /// See [`ColoredString::render`] for details.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(
+        &self,
+        mut writer: W,
+        level: ColorLevel,
+    ) -> io::Result<()> {
+        writer.write_all(self.render(level).as_bytes())
+    }
+
+    fn fmt_at(
+        &self,
+        f: &mut impl fmt::Write,
+        level: ColorLevel,
+        hyperlinks: bool,
+    ) -> fmt::Result {
+        if level == ColorLevel::None {
+            for span in &self.spans {
+                f.write_str(&span.input)?;
+            }
+            return Ok(());
+        }
+
+        let mut current = Rendition::default();
+        let mut current_link: Option<&Hyperlink> = None;
+
+        for span in &self.spans {
+            let link = span.link.as_ref().filter(|_| hyperlinks);
+            if link != current_link {
+                if current_link.is_some() {
+                    Hyperlink::fmt_close(f)?;
+                }
+                if let Some(link) = link {
+                    link.fmt_open(f)?;
+                }
+                current_link = link;
+            }
+
+            let target = Rendition::of(span, level);
+            if let Some(params) = current.transition_to(&target) {
+                write!(f, "\x1B[{params}m")?;
+            }
+            current = target;
+
+            f.write_str(&span.escape_inner_reset_sequences(level))?;
+        }
+
+        if current != Rendition::default() {
+            f.write_str("\x1B[0m")?;
+        }
+        if current_link.is_some() {
+            Hyperlink::fmt_close(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The SGR parameters of a span at some color level, so spans are compared
+/// after their colors have been downgraded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Rendition {
+    style: Style,
+    fg: Option<Cow<'static, str>>,
+    bg: Option<Cow<'static, str>>,
+    underline: Option<Cow<'static, str>>,
+}
+
+impl Rendition {
+    pub(crate) fn of(span: &ColoredString, level: ColorLevel) -> Self {
+        Self {
+            style: span.style,
+            fg: span.fgcolor.map(|c| c.to_fg_str_at(level)),
+            bg: span.bgcolor.map(|c| c.to_bg_str_at(level)),
+            underline: span
+                .underline_color
+                .map(|c| c.to_underline_str_at(level)),
+        }
+    }
+
+    /// Returns the SGR parameters that change `self` to `target`, or `None`
+    /// if they are the same.
+    ///
+    /// This is either the difference between the two, or a reset followed
+    /// by all parameters of `target`, whichever is shorter.
+    pub(crate) fn transition_to(&self, target: &Self) -> Option<String> {
+        if self == target {
+            return None;
+        }
+        if *target == Self::default() {
+            return Some("0".to_owned());
+        }
+
+        let full = format!("0;{}", target.params_from(&Self::default()));
+        let diff = target.params_from(self);
+
+        Some(if diff.len() < full.len() { diff } else { full })
+    }
+
+    /// Returns the parameters that change `from` to `self`.
+    ///
+    /// They are in the same order as [`ColoredString::compute_style`] writes
+    /// them: styles, background, foreground, underline color.
+    fn params_from(&self, from: &Self) -> String {
+        let mut params = Vec::new();
+
+        let styles = from.style.transition_str(self.style);
+        if !styles.is_empty() {
+            params.push(Cow::Owned(styles));
+        }
+
+        let colors = [
+            (&from.bg, &self.bg, "49"),
+            (&from.fg, &self.fg, "39"),
+            (&from.underline, &self.underline, "59"),
+        ];
+        for (from, to, default) in colors {
+            if from != to {
+                params.push(to.clone().unwrap_or(Cow::Borrowed(default)));
+            }
+        }
+
+        params.join(";")
+    }
+}
+
+impl fmt::Display for StyledText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_at(
+            f,
+            control::get_current_color_level(),
+            control::get_hyperlinks(),
+        )
+    }
+}
+
+/// Displays a [`StyledText`] at a fixed color level.
+///
+/// Created by [`StyledText::display_at`].
+#[derive(Clone, Copy, Debug)]
+pub struct StyledDisplay<'r> {
+    inner: &'r StyledText<'r>,
+    level: ColorLevel,
+    hyperlinks: bool,
+}
+
+impl StyledDisplay<'_> {
+    /// Sets whether the links of the spans are written as OSC 8
+    /// hyperlinks.
+    #[must_use]
+    pub fn hyperlinks(mut self, enabled: bool) -> Self {
+        self.hyperlinks = enabled;
+        self
+    }
+}
+
+impl fmt::Display for StyledDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt_at(f, self.level, self.hyperlinks)
+    }
+}
+
+impl<'a> From<Vec<ColoredString<'a>>> for StyledText<'a> {
+    fn from(spans: Vec<ColoredString<'a>>) -> Self {
+        Self { spans }
+    }
+}
+
+impl<'a, S: Into<ColoredString<'a>>> FromIterator<S> for StyledText<'a> {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        Self {
+            spans: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<'a, S: Into<ColoredString<'a>>> Extend<S> for StyledText<'a> {
+    fn extend<T: IntoIterator<Item = S>>(&mut self, iter: T) {
+        self.spans.extend(iter.into_iter().map(Into::into));
+    }
+}
@@ -0,0 +1,326 @@
+//! Reading the color capabilities of a terminal from its terminfo entry.
+//!
+//! The terminfo database describes hundreds of terminals. This module reads
+//! the compiled entries `tic` produces, in both the legacy and the 32-bit
+//! format, including the extended capabilities that announce true color
+//! support, `Tc` and `RGB`.
+//!
+//! With the `terminfo` feature enabled, the entry of `TERM` is used by
+//! [`COLOR_LEVEL_DETECTED`](crate::control::COLOR_LEVEL_DETECTED).
+//!
+//! # Example
+//! ```rust
+//! use cnxt::terminfo::TermInfo;
+//!
+//! if let Some(info) = TermInfo::from_env() {
+//!     println!("{:?} supports {:?} colors", info.names(), info.max_colors());
+//! }
+//! ```
+
+use std::{
+    collections::HashSet,
+    env, fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use crate::control::ColorLevel;
+
+/// Magic number of the legacy format, with 16-bit numbers.
+const MAGIC_LEGACY: i16 = 0o432;
+/// Magic number of the format with 32-bit numbers.
+const MAGIC_32BIT: i16 = 0o1036;
+
+/// Index of the `colors` capability in the numbers section.
+const MAX_COLORS: usize = 13;
+
+/// The parts of a compiled terminfo entry that describe color support.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermInfo {
+    names: Vec<String>,
+    numbers: Vec<i32>,
+    /// Names of the extended capabilities that are present.
+    extended: HashSet<String>,
+}
+
+impl TermInfo {
+    /// Loads the entry of the terminal named by the `TERM` environment
+    /// variable.
+    ///
+    /// Returns `None` if `TERM` is not set or its entry can't be loaded.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let term = env::var("TERM").ok()?;
+        Self::load(&term).ok()
+    }
+
+    /// Loads the entry of the terminal `term` from the terminfo database.
+    ///
+    /// The directories are searched in the same order as ncurses does:
+    /// `$TERMINFO`, `~/.terminfo`, the entries of `$TERMINFO_DIRS`, and
+    /// then the usual system directories such as `/usr/share/terminfo`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`ErrorKind::NotFound`] if there is no
+    /// entry for `term`, or the error from [`TermInfo::parse`] if the entry
+    /// is invalid.
+    pub fn load(term: &str) -> io::Result<Self> {
+        if term.is_empty()
+            || term.contains(['/', '\\'])
+            || term.starts_with('.')
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "invalid terminal name",
+            ));
+        }
+
+        let first = term.as_bytes()[0];
+        for dir in search_dirs() {
+            // Entries are grouped by their first letter, or its hex code on
+            // case-insensitive file systems
+            for subdir in [(first as char).to_string(), format!("{first:02x}")]
+            {
+                if let Ok(bytes) = fs::read(dir.join(subdir).join(term)) {
+                    return Self::parse(&bytes);
+                }
+            }
+        }
+
+        Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("no terminfo entry for {term}"),
+        ))
+    }
+
+    /// Parses a compiled terminfo entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidData`] if `bytes` is not
+    /// a valid entry.
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let mut r = Reader { bytes, pos: 0 };
+
+        let wide = match r.i16()? {
+            MAGIC_LEGACY => false,
+            MAGIC_32BIT => true,
+            _ => return Err(invalid_data("unknown terminfo magic number")),
+        };
+        let names_size = r.count()?;
+        let bools_count = r.count()?;
+        let numbers_count = r.count()?;
+        let strings_count = r.count()?;
+        let table_size = r.count()?;
+
+        let names = cstr(r.take(names_size)?, 0)
+            .split('|')
+            .map(str::to_owned)
+            .collect();
+        r.take(bools_count)?;
+        r.align();
+        let numbers = (0..numbers_count)
+            .map(|_| r.number(wide))
+            .collect::<io::Result<_>>()?;
+        r.take(strings_count * 2)?;
+        r.take(table_size)?;
+
+        // The extended capabilities are optional
+        r.align();
+        let extended = if r.remaining() > 0 {
+            parse_extended(&mut r, wide)?
+        } else {
+            HashSet::new()
+        };
+
+        Ok(Self {
+            names,
+            numbers,
+            extended,
+        })
+    }
+
+    /// Returns the names of the terminal, the primary one first.
+    #[must_use]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the `colors` capability, the number of colors the terminal
+    /// supports.
+    #[must_use]
+    pub fn max_colors(&self) -> Option<u32> {
+        let colors = *self.numbers.get(MAX_COLORS)?;
+        u32::try_from(colors).ok()
+    }
+
+    /// Returns true if the extended capability `name` is present, e.g.
+    /// `Tc` or `RGB`.
+    #[must_use]
+    pub fn has_extended(&self, name: &str) -> bool {
+        self.extended.contains(name)
+    }
+
+    /// Returns the color level of the terminal, or `None` if the entry
+    /// has no `colors` capability.
+    ///
+    /// The extended `Tc` and `RGB` capabilities, as well as 2²⁴ or more
+    /// colors, mean true color support.
+    #[must_use]
+    pub fn color_level(&self) -> Option<ColorLevel> {
+        if self.has_extended("Tc") || self.has_extended("RGB") {
+            return Some(ColorLevel::TrueColor);
+        }
+
+        let level = match self.max_colors()? {
+            0x100_0000.. => ColorLevel::TrueColor,
+            256.. => ColorLevel::Ansi256,
+            8.. => ColorLevel::Ansi16,
+            _ => ColorLevel::None,
+        };
+        Some(level)
+    }
+}
+
+/// Parses the extended capabilities section, returning the names of the
+/// capabilities that are present.
+fn parse_extended(r: &mut Reader, wide: bool) -> io::Result<HashSet<String>> {
+    let bools_count = r.count()?;
+    let numbers_count = r.count()?;
+    let strings_count = r.count()?;
+    let _items_count = r.count()?;
+    let table_size = r.count()?;
+
+    let bools = r.take(bools_count)?;
+    r.align();
+    let numbers = (0..numbers_count)
+        .map(|_| r.number(wide))
+        .collect::<io::Result<Vec<_>>>()?;
+    let strings = (0..strings_count)
+        .map(|_| r.i16())
+        .collect::<io::Result<Vec<_>>>()?;
+    let name_offsets = (0..bools_count + numbers_count + strings_count)
+        .map(|_| r.i16())
+        .collect::<io::Result<Vec<_>>>()?;
+    let table = r.take(table_size)?;
+
+    // The names follow the values of the strings in the table
+    let names_start = strings
+        .iter()
+        .filter_map(|&offset| usize::try_from(offset).ok())
+        .map(|offset| offset + cstr(table, offset).len() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let present = bools
+        .iter()
+        .map(|&value| value == 1)
+        .chain(numbers.iter().map(|&value| value >= 0))
+        .chain(strings.iter().map(|&offset| offset >= 0));
+
+    Ok(name_offsets
+        .iter()
+        .zip(present)
+        .filter(|&(_, present)| present)
+        .filter_map(|(&offset, _)| {
+            let offset = usize::try_from(offset).ok()?;
+            Some(cstr(table, names_start + offset).to_owned())
+        })
+        .collect())
+}
+
+/// Returns the directories to look for terminfo entries in.
+fn search_dirs() -> Vec<PathBuf> {
+    const SYSTEM_DIRS: [&str; 5] = [
+        "/etc/terminfo",
+        "/lib/terminfo",
+        "/usr/share/terminfo",
+        "/usr/lib/terminfo",
+        "/usr/local/share/terminfo",
+    ];
+
+    let mut dirs = Vec::new();
+
+    if let Some(dir) = env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    if let Ok(terminfo_dirs) = env::var("TERMINFO_DIRS") {
+        for dir in terminfo_dirs.split(':') {
+            // An empty entry stands for the system directories
+            if dir.is_empty() {
+                dirs.extend(SYSTEM_DIRS.map(PathBuf::from));
+            } else {
+                dirs.push(PathBuf::from(dir));
+            }
+        }
+    }
+    dirs.extend(SYSTEM_DIRS.map(PathBuf::from));
+
+    dirs
+}
+
+/// Returns the NUL-terminated string at `offset` in `table`, or an empty
+/// string if it's out of bounds.
+fn cstr(table: &[u8], offset: usize) -> &str {
+    let bytes = table.get(offset..).unwrap_or_default();
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).unwrap_or_default()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+/// A cursor over a compiled entry. All numbers are little endian.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.pos)
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| invalid_data("truncated terminfo entry"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Skips the padding byte that aligns sections to an even offset.
+    fn align(&mut self) {
+        self.pos += self.pos % 2;
+    }
+
+    fn i16(&mut self) -> io::Result<i16> {
+        let bytes = self.take(2)?;
+        Ok(i16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Reads a count from a header, where `-1` means none.
+    fn count(&mut self) -> io::Result<usize> {
+        match self.i16()? {
+            -1 => Ok(0),
+            count => usize::try_from(count)
+                .map_err(|_| invalid_data("negative count in terminfo header")),
+        }
+    }
+
+    /// Reads a number capability, where negative values mean it's absent.
+    fn number(&mut self, wide: bool) -> io::Result<i32> {
+        if wide {
+            let bytes = self.take(4)?;
+            Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        } else {
+            self.i16().map(i32::from)
+        }
+    }
+}
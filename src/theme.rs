@@ -0,0 +1,673 @@
+//! Semantic roles, and the themes that decide how they look.
+//!
+//! Instead of hardcoding `.red().bold()` at every call site, text can be
+//! given a role with [`Colorize::role`], which looks up the style of that
+//! role in the active [`Theme`]. Swapping the theme then restyles the whole
+//! application consistently.
+//!
+//! The active theme starts out as [`Theme::from_env()`], the built-in roles
+//! with the overrides from the `CNXT_THEME` environment variable applied. It
+//! can be replaced with [`set_theme()`], or for the current thread only with
+//! [`with_theme()`] or [`override_theme()`].
+//!
+//! # Example
+//! ```rust
+//! use cnxt::{
+//!     Colorize as _,
+//!     control::ColorLevel,
+//!     theme::{Theme, with_theme},
+//! };
+//!
+//! let theme: Theme = "error = bold bright_red; path = cyan underline"
+//!     .parse()
+//!     .unwrap();
+//!
+//! with_theme(theme, || {
+//!     let msg = "file not found".role("error");
+//!     assert_eq!(msg.render(ColorLevel::Ansi16), "\x1B[1;91mfile not found\x1B[0m");
+//! });
+//! ```
+//!
+//! [`Colorize::role`]: crate::Colorize::role
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    error::Error,
+    fmt,
+    marker::PhantomData,
+    str::FromStr,
+    sync::{Arc, LazyLock, PoisonError, RwLock},
+};
+
+use crate::{
+    Color, ColoredString, ParseColorError, ParseStyleError, Style, Styles,
+};
+
+/// The environment variable that overrides roles of the initial theme.
+///
+/// It holds `role=spec` entries separated by `;`, in the format described
+/// at [`Theme::from_str`], e.g. `error=bold magenta;path=blue`.
+pub const THEME_ENV: &str = "CNXT_THEME";
+
+/// A combination of foreground color, background color and style.
+///
+/// Colors that are `None` leave the colors of the text unchanged when the
+/// spec is applied.
+///
+/// The builder methods take anything that converts into a color or style,
+/// which rules them out in a `const`. There, a spec is assembled from its
+/// fields instead:
+///
+/// ```rust
+/// use cnxt::{Color, Style, theme::StyleSpec};
+///
+/// const WARNING: StyleSpec = StyleSpec {
+///     fg: Some(Color::from_hex_const("#ff8800")),
+///     style: Style::new().bold(),
+///     ..StyleSpec::new()
+/// };
+/// assert_eq!(WARNING, "bold #ff8800".parse().unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StyleSpec {
+    /// The foreground color to apply, if any.
+    pub fg: Option<Color>,
+    /// The background color to apply, if any.
+    pub bg: Option<Color>,
+    /// The styles to add.
+    pub style: Style,
+}
+
+impl StyleSpec {
+    /// Creates a spec that changes nothing.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            fg: None,
+            bg: None,
+            style: Style::new(),
+        }
+    }
+
+    /// Sets the foreground color.
+    #[must_use]
+    pub fn fg(mut self, color: impl Into<Color>) -> Self {
+        self.fg = Some(color.into());
+        self
+    }
+
+    /// Sets the background color.
+    #[must_use]
+    pub fn bg(mut self, color: impl Into<Color>) -> Self {
+        self.bg = Some(color.into());
+        self
+    }
+
+    /// Adds `style` to the styles of this spec. An underline style replaces
+    /// the one of the spec.
+    #[must_use]
+    pub fn style(mut self, style: impl Into<Style>) -> Self {
+        self.style.merge(style.into());
+        self
+    }
+
+    /// Applies this spec to `s`.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, Colorize as _, Styles, theme::StyleSpec};
+    ///
+    /// let spec = StyleSpec::new().fg(Color::Red).style(Styles::Bold);
+    /// let s = spec.apply("text".italic());
+    ///
+    /// assert_eq!(s.fgcolor, Some(Color::Red));
+    /// assert!(s.style.contains(Styles::Bold));
+    /// assert!(s.style.contains(Styles::Italic));
+    /// ```
+    pub fn apply<'a>(
+        &self,
+        s: impl Into<ColoredString<'a>>,
+    ) -> ColoredString<'a> {
+        let mut s = s.into();
+        if self.fg.is_some() {
+            s.fgcolor = self.fg;
+        }
+        if self.bg.is_some() {
+            s.bgcolor = self.bg;
+        }
+        s.style.merge(self.style);
+        s
+    }
+}
+
+impl FromStr for StyleSpec {
+    type Err = ParseThemeError;
+
+    /// Parses a spec from a list of words, such as `bold red on black`.
+    ///
+    /// Each word is either a style name, see [`Styles::from_str`], or a
+    /// color, see [`Color::from_str`]. The color after `on` is the
+    /// background color. Words are separated by whitespace, and colors may
+    /// be written as `bright red` as well.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, Styles, theme::StyleSpec};
+    ///
+    /// let spec: StyleSpec = "bold bright red on rgb(0, 0, 64)".parse().unwrap();
+    /// assert_eq!(spec.fg, Some(Color::BrightRed));
+    /// assert_eq!(spec.bg, Some(Color::TrueColor { r: 0, g: 0, b: 64 }));
+    /// assert!(spec.style.contains(Styles::Bold));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spec = Self::new();
+        let mut words = SpecWords { rest: s };
+
+        while let Some(word) = words.next() {
+            if word.eq_ignore_ascii_case("on") {
+                let color =
+                    words.next_color().ok_or(ParseThemeError::MissingColor)?;
+                spec.bg = Some(parse_color(&color)?);
+            } else if let Ok(style) = word.parse::<Styles>() {
+                spec.style.merge(style.into());
+            } else {
+                let color = words.with_bright_prefix(word);
+                spec.fg = Some(parse_color(&color)?);
+            }
+        }
+
+        Ok(spec)
+    }
+}
+
+fn parse_color(word: &str) -> Result<Color, ParseThemeError> {
+    word.parse().map_err(|err| match err {
+        ParseColorError::UnknownName => {
+            ParseThemeError::UnknownWord(word.to_owned())
+        }
+        err => ParseThemeError::Color(err),
+    })
+}
+
+/// Splits a spec into words at whitespace, keeping the arguments of color
+/// functions like `rgb(1, 2, 3)` together.
+struct SpecWords<'a> {
+    rest: &'a str,
+}
+
+impl<'a> SpecWords<'a> {
+    fn next_color(&mut self) -> Option<String> {
+        let word = self.next()?;
+        Some(self.with_bright_prefix(word))
+    }
+
+    /// Joins `bright` with the following word, so `bright red` is one color.
+    fn with_bright_prefix(&mut self, word: &str) -> String {
+        if word.eq_ignore_ascii_case("bright")
+            && let Some(next) = self.next()
+        {
+            format!("bright {next}")
+        } else {
+            word.to_owned()
+        }
+    }
+}
+
+impl<'a> Iterator for SpecWords<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let s = self.rest.trim_start();
+        if s.is_empty() {
+            return None;
+        }
+
+        let mut depth = 0usize;
+        let end = s
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth = depth.saturating_sub(1),
+                    c if c.is_whitespace() && depth == 0 => return true,
+                    _ => {}
+                }
+                false
+            })
+            .map_or(s.len(), |(idx, _)| idx);
+
+        let (word, rest) = s.split_at(end);
+        self.rest = rest;
+        Some(word)
+    }
+}
+
+/// A mapping from semantic roles to the way they are styled.
+///
+/// [`Theme::new()`] comes with these roles:
+///
+/// | Role       | Spec          |
+/// |------------|---------------|
+/// | `error`    | `bold red`    |
+/// | `warning`  | `bold yellow` |
+/// | `info`     | `bold blue`   |
+/// | `success`  | `bold green`  |
+/// | `hint`     | `cyan`        |
+/// | `path`     | `underline`   |
+/// | `code`     | `yellow`      |
+/// | `emphasis` | `bold`        |
+///
+/// Any other role can be added with [`Theme::insert`].
+///
+/// ```rust
+/// use cnxt::{Color, theme::{StyleSpec, Theme}};
+///
+/// let mut theme = Theme::new();
+/// theme.insert("version", StyleSpec::new().fg(Color::Magenta));
+///
+/// assert_eq!(theme.get("version"), Some(StyleSpec::new().fg(Color::Magenta)));
+/// assert!(theme.get("error").is_some());
+/// assert!(theme.get("unknown").is_none());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    roles: HashMap<String, StyleSpec>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Theme {
+    /// Creates a theme with the built-in roles.
+    #[must_use]
+    pub fn new() -> Self {
+        let bold = Style::from(Styles::Bold);
+        let roles = [
+            ("error", StyleSpec::new().fg(Color::Red).style(bold)),
+            ("warning", StyleSpec::new().fg(Color::Yellow).style(bold)),
+            ("info", StyleSpec::new().fg(Color::Blue).style(bold)),
+            ("success", StyleSpec::new().fg(Color::Green).style(bold)),
+            ("hint", StyleSpec::new().fg(Color::Cyan)),
+            ("path", StyleSpec::new().style(Styles::Underline)),
+            ("code", StyleSpec::new().fg(Color::Yellow)),
+            ("emphasis", StyleSpec::new().style(bold)),
+        ];
+
+        Self {
+            roles: roles
+                .into_iter()
+                .map(|(role, spec)| (role.to_owned(), spec))
+                .collect(),
+        }
+    }
+
+    /// Creates a theme without any roles.
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            roles: HashMap::new(),
+        }
+    }
+
+    /// Creates a theme with the built-in roles, overridden by the roles in
+    /// the [`THEME_ENV`] environment variable.
+    ///
+    /// The variable is ignored if it cannot be parsed.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let mut theme = Self::new();
+        if let Some(overrides) = env::var(THEME_ENV)
+            .ok()
+            .and_then(|var| var.parse::<Self>().ok())
+        {
+            theme.merge(overrides);
+        }
+        theme
+    }
+
+    /// Returns the spec of `role`, if the theme has it.
+    #[must_use]
+    pub fn get(&self, role: &str) -> Option<StyleSpec> {
+        self.roles.get(role).copied()
+    }
+
+    /// Sets the spec of `role`, returning the previous one.
+    pub fn insert(
+        &mut self,
+        role: impl Into<String>,
+        spec: StyleSpec,
+    ) -> Option<StyleSpec> {
+        self.roles.insert(role.into(), spec)
+    }
+
+    /// Sets the spec of `role`, for chaining.
+    #[must_use]
+    pub fn with_role(
+        mut self,
+        role: impl Into<String>,
+        spec: StyleSpec,
+    ) -> Self {
+        self.insert(role, spec);
+        self
+    }
+
+    /// Removes `role` from the theme, returning its spec.
+    pub fn remove(&mut self, role: &str) -> Option<StyleSpec> {
+        self.roles.remove(role)
+    }
+
+    /// Adds all roles of `other` to this theme, replacing existing ones.
+    pub fn merge(&mut self, other: Self) {
+        self.roles.extend(other.roles);
+    }
+
+    /// Iterates over the roles of the theme and their specs, in no
+    /// particular order.
+    pub fn roles(&self) -> impl Iterator<Item = (&str, StyleSpec)> {
+        self.roles.iter().map(|(role, &spec)| (role.as_str(), spec))
+    }
+
+    /// Applies the spec of `role` to `s`.
+    ///
+    /// `s` is returned unchanged if the theme does not have `role`.
+    pub fn apply<'a>(
+        &self,
+        role: &str,
+        s: impl Into<ColoredString<'a>>,
+    ) -> ColoredString<'a> {
+        match self.get(role) {
+            Some(spec) => spec.apply(s),
+            None => s.into(),
+        }
+    }
+
+    /// Loads a theme from TOML.
+    ///
+    /// Each key is a role, and its value is either a spec string as
+    /// described at [`StyleSpec::from_str`], or a table with optional `fg`,
+    /// `bg` and `style` keys. `style` is a string of style names or a list
+    /// of them. The theme only has the roles from the document, use
+    /// [`Theme::merge`] to add them to another theme.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, Styles, theme::Theme};
+    ///
+    /// let theme = Theme::from_toml(r##"
+    ///     error = "bold bright_red"
+    ///
+    ///     [path]
+    ///     fg = "#8aadf4"
+    ///     style = ["italic", "underline"]
+    /// "##).unwrap();
+    ///
+    /// let path = theme.get("path").unwrap();
+    /// assert_eq!(path.fg, Some(Color::TrueColor { r: 0x8a, g: 0xad, b: 0xf4 }));
+    /// assert!(path.style.contains(Styles::Underline));
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> Result<Self, ParseThemeError> {
+        let raw: HashMap<String, raw::Spec> =
+            toml::from_str(s).map_err(ParseThemeError::Toml)?;
+        raw::to_theme(raw)
+    }
+
+    /// Loads a theme from JSON.
+    ///
+    /// The document is an object with the same structure as described at
+    /// [`Theme::from_toml`].
+    ///
+    /// ```rust
+    /// use cnxt::{Color, theme::Theme};
+    ///
+    /// let theme = Theme::from_json(r#"{
+    ///     "error": "bold red",
+    ///     "hint": { "fg": "bright black", "style": "italic" }
+    /// }"#).unwrap();
+    ///
+    /// assert_eq!(theme.get("hint").unwrap().fg, Some(Color::BrightBlack));
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_json(s: &str) -> Result<Self, ParseThemeError> {
+        let raw: HashMap<String, raw::Spec> =
+            serde_json::from_str(s).map_err(ParseThemeError::Json)?;
+        raw::to_theme(raw)
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ParseThemeError;
+
+    /// Parses a theme from `role=spec` entries, separated by `;` or new
+    /// lines.
+    ///
+    /// The specs are parsed as described at [`StyleSpec::from_str`]. Empty
+    /// entries are skipped. The theme only has the listed roles.
+    ///
+    /// ```rust
+    /// use cnxt::{Color, theme::Theme};
+    ///
+    /// let theme: Theme = "error=bold magenta; path=blue".parse().unwrap();
+    /// assert_eq!(theme.get("path").unwrap().fg, Some(Color::Blue));
+    /// assert!(theme.get("warning").is_none());
+    ///
+    /// assert!("error".parse::<Theme>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut theme = Self::empty();
+
+        for entry in s.split([';', '\n']) {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+
+            let (role, spec) = entry
+                .split_once('=')
+                .map(|(role, spec)| (role.trim(), spec))
+                .filter(|(role, _)| !role.is_empty())
+                .ok_or_else(|| {
+                    ParseThemeError::InvalidEntry(entry.to_owned())
+                })?;
+            theme.insert(role, spec.parse()?);
+        }
+
+        Ok(theme)
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+mod raw {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::{ParseThemeError, StyleSpec, Theme, parse_color};
+    use crate::Styles;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum Spec {
+        Words(String),
+        Table(Table),
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub(super) struct Table {
+        fg: Option<String>,
+        bg: Option<String>,
+        style: Option<StyleNames>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum StyleNames {
+        Words(String),
+        List(Vec<String>),
+    }
+
+    pub(super) fn to_theme(
+        raw: HashMap<String, Spec>,
+    ) -> Result<Theme, ParseThemeError> {
+        let mut theme = Theme::empty();
+        for (role, spec) in raw {
+            theme.insert(role, to_spec(spec)?);
+        }
+        Ok(theme)
+    }
+
+    fn to_spec(raw: Spec) -> Result<StyleSpec, ParseThemeError> {
+        let Table { fg, bg, style } = match raw {
+            Spec::Words(words) => return words.parse(),
+            Spec::Table(table) => table,
+        };
+
+        let mut spec = StyleSpec {
+            fg: fg.as_deref().map(parse_color).transpose()?,
+            bg: bg.as_deref().map(parse_color).transpose()?,
+            ..StyleSpec::new()
+        };
+
+        let names = match style {
+            Some(StyleNames::Words(words)) => {
+                words.split_whitespace().map(str::to_owned).collect()
+            }
+            Some(StyleNames::List(names)) => names,
+            None => Vec::new(),
+        };
+        for name in names {
+            let style =
+                name.parse::<Styles>().map_err(ParseThemeError::Style)?;
+            spec.style.merge(style.into());
+        }
+
+        Ok(spec)
+    }
+}
+
+/// An error which can be returned when parsing a [`Theme`] or
+/// [`StyleSpec`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParseThemeError {
+    /// An entry is not of the form `role=spec`.
+    InvalidEntry(String),
+    /// A word of a spec is neither a style nor a color.
+    UnknownWord(String),
+    /// `on` is not followed by a color.
+    MissingColor,
+    /// A color could not be parsed.
+    Color(ParseColorError),
+    /// A style could not be parsed.
+    Style(ParseStyleError),
+    /// The TOML document is invalid.
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// The JSON document is invalid.
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ParseThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEntry(entry) => {
+                write!(f, "expected `role=spec`, found `{entry}`")
+            }
+            Self::UnknownWord(word) => {
+                write!(f, "`{word}` is neither a style nor a color")
+            }
+            Self::MissingColor => f.write_str("expected a color after `on`"),
+            Self::Color(err) => err.fmt(f),
+            Self::Style(err) => err.fmt(f),
+            #[cfg(feature = "toml")]
+            Self::Toml(err) => err.fmt(f),
+            #[cfg(feature = "json")]
+            Self::Json(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ParseThemeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Color(err) => Some(err),
+            Self::Style(err) => Some(err),
+            #[cfg(feature = "toml")]
+            Self::Toml(err) => Some(err),
+            #[cfg(feature = "json")]
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The process-wide active theme.
+static THEME: LazyLock<RwLock<Arc<Theme>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(Theme::from_env())));
+
+thread_local! {
+    static THEME_OVERRIDE: RefCell<Option<Arc<Theme>>> =
+        const { RefCell::new(None) };
+}
+
+/// Sets the process-wide active theme.
+///
+/// Default value is generated by [`Theme::from_env()`].
+pub fn set_theme(theme: Theme) {
+    *THEME.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(theme);
+}
+
+/// Gets the active theme.
+///
+/// A scoped override set on the current thread with [`with_theme()`] or
+/// [`override_theme()`] takes precedence over the process-wide theme.
+#[must_use]
+pub fn get_theme() -> Arc<Theme> {
+    THEME_OVERRIDE
+        .with_borrow(Option::clone)
+        .unwrap_or_else(|| {
+            THEME.read().unwrap_or_else(PoisonError::into_inner).clone()
+        })
+}
+
+/// Overrides the active theme on the current thread until the returned
+/// guard is dropped.
+///
+/// Dropping the guard restores whatever theme was in effect before, so
+/// overrides can be nested.
+pub fn override_theme(theme: Theme) -> ThemeGuard {
+    ThemeGuard {
+        previous: THEME_OVERRIDE.replace(Some(Arc::new(theme))),
+        _not_send: PhantomData,
+    }
+}
+
+/// Runs `f` with the active theme overridden on the current thread.
+///
+/// See [`override_theme()`] for details.
+pub fn with_theme<R>(theme: Theme, f: impl FnOnce() -> R) -> R {
+    let _guard = override_theme(theme);
+    f()
+}
+
+/// Guard returned by [`override_theme()`].
+///
+/// Restores the previous thread-local theme when dropped.
+#[must_use = "the override is removed as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ThemeGuard {
+    previous: Option<Arc<Theme>>,
+    // The override lives in a thread local, so the guard must stay on the
+    // thread that created it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ThemeGuard {
+    fn drop(&mut self) {
+        THEME_OVERRIDE.set(self.previous.take());
+    }
+}